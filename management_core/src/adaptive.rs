@@ -0,0 +1,350 @@
+use std::collections::{BTreeMap, HashMap};
+use serde::Serialize;
+
+use crate::{
+    AssessmentSession, CoefficientScheme, Question, QuestionGrade, QuestionStatistics, QuestionView, QuizAnswerRecord,
+    QuizError, SubmittedAnswer
+};
+
+/// Длина адаптивного теста по умолчанию, как у обычного теста
+pub const DEFAULT_ADAPTIVE_LENGTH: usize = 15;
+
+/// Сколько ответов «весит» сложность из схемы против накопленной статистики
+const PRIOR_WEIGHT: f64 = 5.0;
+
+/// Начальный шаг обновления способностей. Уменьшается с каждым ответом.
+const ELO_K: f64 = 0.8;
+
+/// Сложность вопроса в логитах модели Раша: сложность из схемы (1–5, по
+/// умолчанию 3) сдвигается к наблюдаемой доле верных ответов
+pub fn estimate_difficulty(question: &Question, statistics: Option<&QuestionStatistics>) -> f64 {
+    let prior = question.get_difficulty().map_or(0.0, |difficulty| difficulty as f64 - 3.0);
+
+    let statistics = match statistics {
+        Some(statistics) if statistics.attempts > 0 => statistics,
+        _ => return prior
+    };
+
+    // Сглаживание Лапласа, чтобы 0 и 100% верных ответов не давали бесконечность
+    let attempts = statistics.attempts as f64;
+    let success = (statistics.credit + 1.0) / (attempts + 2.0);
+    let observed = ((1.0 - success) / success).ln();
+
+    (PRIOR_WEIGHT * prior + attempts * observed) / (PRIOR_WEIGHT + attempts)
+}
+
+/// Вероятность верного ответа при способностях `ability` и сложности `difficulty`
+fn expected_credit(ability: f64, difficulty: f64) -> f64 {
+    1.0 / (1.0 + (difficulty - ability).exp())
+}
+
+/// Состояние адаптивного теста одной сессии
+#[derive(Debug, Clone)]
+pub struct AdaptiveQuiz {
+    ability: f64,
+    length: usize,
+    /// Сложность каждого вопроса банка на момент начала теста
+    difficulties: BTreeMap<String, f64>,
+    asked: Vec<String>,
+    pending: Option<String>,
+    grades: Vec<QuestionGrade>
+}
+
+/// Ответ на шаг адаптивного теста. `question == None` — тест окончен.
+#[derive(Debug, Clone, Serialize)]
+pub struct AdaptiveStep {
+    pub question: Option<QuestionView>,
+    /// Оценка только что отправленного ответа
+    pub last: Option<QuestionGrade>,
+    pub answered: usize,
+    pub length: usize,
+    /// Текущая оценка способностей в логитах, 0 — средний уровень банка
+    pub ability: f64
+}
+
+impl AdaptiveQuiz {
+    pub fn new(scheme: &CoefficientScheme, statistics: &HashMap<String, QuestionStatistics>, length: usize) -> Self {
+        let difficulties = scheme
+            .get_questions()
+            .iter()
+            .map(|question| (
+                question.get_uuid().clone(),
+                estimate_difficulty(question, statistics.get(question.get_uuid()))
+            ))
+            .collect();
+
+        Self {
+            ability: 0.0,
+            length,
+            difficulties,
+            asked: vec![],
+            pending: None,
+            grades: vec![]
+        }
+    }
+
+    /// Повторяет сохранённые ответы сессии по порядку, как если бы они
+    /// пришли заново. Статистика с тех пор могла измениться, поэтому
+    /// способности и следующий вопрос могут немного отличаться.
+    fn replay(
+        scheme: &CoefficientScheme,
+        statistics: &HashMap<String, QuestionStatistics>,
+        length: usize,
+        answers: &[QuizAnswerRecord]
+    ) -> Self {
+
+        // К началу теста собственных ответов сессии в статистике не было
+        let mut statistics = statistics.clone();
+        for answer in answers {
+            if let Some(statistics) = statistics.get_mut(&answer.question_uuid) {
+                statistics.attempts = statistics.attempts.saturating_sub(1);
+                statistics.credit = (statistics.credit - answer.credit).max(0.0);
+            }
+        }
+
+        let mut quiz = AdaptiveQuiz::new(scheme, &statistics, length);
+        for answer in answers {
+            if let Some(question) = scheme.get_questions().get(&answer.question_uuid) {
+                quiz.asked.push(answer.question_uuid.clone());
+                quiz.answer(question, answer.selected.clone());
+            }
+        }
+
+        quiz
+    }
+
+    pub fn get_ability(&self) -> f64 {
+        self.ability
+    }
+
+    pub fn get_grades(&self) -> &[QuestionGrade] {
+        &self.grades
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.pending.is_none() && !self.asked.is_empty()
+    }
+
+    /// Ещё не заданный вопрос со сложностью, ближайшей к текущим способностям:
+    /// у такого вопроса наибольшая информативность в модели Раша
+    fn pick_next(&self) -> Option<String> {
+        if self.asked.len() >= self.length {
+            return None;
+        }
+
+        self.difficulties
+            .iter()
+            .filter(|(uuid, _)| !self.asked.contains(uuid))
+            .min_by(|(_, a), (_, b)| (*a - self.ability).abs().total_cmp(&(*b - self.ability).abs()))
+            .map(|(uuid, _)| uuid.clone())
+    }
+
+    fn advance(&mut self, scheme: &CoefficientScheme, last: Option<QuestionGrade>) -> AdaptiveStep {
+        self.pending = self.pick_next();
+        if let Some(uuid) = &self.pending {
+            self.asked.push(uuid.clone());
+        }
+
+        self.step(scheme, last)
+    }
+
+    fn step(&self, scheme: &CoefficientScheme, last: Option<QuestionGrade>) -> AdaptiveStep {
+        AdaptiveStep {
+            question: self.pending
+                .as_ref()
+                .and_then(|uuid| scheme.get_questions().get(uuid))
+                .map(QuestionView::from),
+            last,
+            answered: self.grades.len(),
+            length: self.length.min(self.difficulties.len()),
+            ability: self.ability
+        }
+    }
+
+    /// Оценивает ответ на текущий вопрос и обновляет способности по Эло
+    fn answer(&mut self, question: &Question, selected: Vec<String>) -> QuestionGrade {
+        let grade = question.grade(selected);
+
+        let difficulty = self.difficulties.get(question.get_uuid()).copied().unwrap_or_default();
+        let step = ELO_K / (1.0 + self.grades.len() as f64).sqrt();
+        self.ability += step * (grade.credit - expected_credit(self.ability, difficulty));
+
+        self.grades.push(grade.clone());
+
+        grade
+    }
+}
+
+/// Адаптивные тесты по сессиям
+#[derive(Debug, Default)]
+pub struct AdaptiveSessions {
+    sessions: HashMap<i64, AdaptiveQuiz>
+}
+
+impl AdaptiveSessions {
+    /// Начинает тест и возвращает первый вопрос. Тест сессии начинается один
+    /// раз: перезапуск сбросил бы оценку способностей при сохранённых ответах.
+    pub fn start(
+        &mut self,
+        session_id: i64,
+        scheme: &CoefficientScheme,
+        statistics: &HashMap<String, QuestionStatistics>,
+        length: usize
+    ) -> Result<AdaptiveStep, QuizError> {
+
+        match self.sessions.get(&session_id) {
+            Some(quiz) if quiz.is_finished() => return Err(QuizError::AlreadySubmitted { session_id }),
+            Some(_) => return Err(QuizError::AlreadyIssued { session_id }),
+            None => {}
+        }
+
+        let mut quiz = AdaptiveQuiz::new(scheme, statistics, length);
+        let step = quiz.advance(scheme, None);
+        self.sessions.insert(session_id, quiz);
+
+        Ok(step)
+    }
+
+    /// Принимает ответ на текущий вопрос и возвращает следующий
+    pub fn answer(
+        &mut self,
+        session_id: i64,
+        scheme: &CoefficientScheme,
+        answer: SubmittedAnswer
+    ) -> Result<AdaptiveStep, QuizError> {
+
+        let quiz = self.sessions
+            .get_mut(&session_id)
+            .ok_or(QuizError::NotIssued { session_id })?;
+
+        let pending = match &quiz.pending {
+            Some(pending) => pending,
+            None => return Err(QuizError::AlreadySubmitted { session_id })
+        };
+
+        if pending != &answer.question_uuid {
+            return Err(QuizError::QuestionNotIssued {
+                session_id,
+                question_uuid: answer.question_uuid
+            });
+        }
+
+        // Вопрос, пропавший из схемы после выдачи, пропускается без оценки
+        let last = scheme
+            .get_questions()
+            .get(&answer.question_uuid)
+            .map(|question| quiz.answer(question, answer.answers));
+
+        Ok(quiz.advance(scheme, last))
+    }
+
+    /// Восстанавливает начатый тест сессии по длине из базы и сохранённым
+    /// ответам, если его нет в памяти, например после перезапуска приложения
+    pub fn restore(
+        &mut self,
+        session: &AssessmentSession,
+        scheme: &CoefficientScheme,
+        statistics: &HashMap<String, QuestionStatistics>,
+        answers: &[QuizAnswerRecord]
+    ) {
+        let length = match session.adaptive_length {
+            Some(length) if !self.sessions.contains_key(&session.id) => length,
+            _ => return
+        };
+
+        let mut quiz = AdaptiveQuiz::replay(scheme, statistics, length, answers);
+        quiz.advance(scheme, None);
+        self.sessions.insert(session.id, quiz);
+    }
+
+    /// Текущий вопрос начатого теста, например для окна студента после
+    /// перезапуска приложения
+    pub fn current(&self, session_id: i64, scheme: &CoefficientScheme) -> Result<AdaptiveStep, QuizError> {
+        let quiz = self.sessions
+            .get(&session_id)
+            .ok_or(QuizError::NotIssued { session_id })?;
+
+        if quiz.is_finished() {
+            return Err(QuizError::AlreadySubmitted { session_id });
+        }
+
+        Ok(quiz.step(scheme, None))
+    }
+
+    pub fn get(&self, session_id: i64) -> Option<&AdaptiveQuiz> {
+        self.sessions.get(&session_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+    use crate::{fixtures, question_uuid, Storage};
+    use super::*;
+
+    #[test]
+    fn adaptive_quiz_follows_ability() {
+
+        let questions = (1..=5)
+            .map(|difficulty| json!({
+                "title": format!("Сложность {}", difficulty),
+                "difficulty": difficulty,
+                "variants": [
+                    { "id": "yes", "content": "Да", "is_answer": true },
+                    { "id": "no", "content": "Нет", "is_answer": false }
+                ]
+            }))
+            .collect::<Vec<Value>>();
+        let schema = fixtures::scheme(json!({ "questions": questions }));
+        let uuid = |difficulty: u8| question_uuid(&format!("Сложность {}", difficulty));
+
+        let mut sessions = AdaptiveSessions::default();
+        let first = sessions.start(1, &schema, &HashMap::new(), 3).unwrap();
+        assert!(matches!(sessions.start(1, &schema, &HashMap::new(), 3), Err(QuizError::AlreadyIssued { .. })));
+        assert_eq!(first.question.as_ref().unwrap().uuid, uuid(3));
+
+        let wrong = SubmittedAnswer { question_uuid: uuid(5), answers: vec!["yes".into()] };
+        assert!(matches!(sessions.answer(1, &schema, wrong), Err(QuizError::QuestionNotIssued { .. })));
+
+        let right = SubmittedAnswer { question_uuid: uuid(3), answers: vec!["yes".into()] };
+        let second = sessions.answer(1, &schema, right).unwrap();
+        assert!(second.ability > 0.0);
+        assert_eq!(second.question.as_ref().unwrap().uuid, uuid(4));
+
+        // Все отвечали на вопрос сложности 1 неверно, поэтому он оказывается труднее вопроса 2
+        let mut statistics = HashMap::new();
+        statistics.insert(uuid(1), QuestionStatistics { attempts: 100, credit: 0.0 });
+        let hard = schema.get_questions().get(&uuid(1)).unwrap();
+        let medium = schema.get_questions().get(&uuid(2)).unwrap();
+        assert!(estimate_difficulty(hard, statistics.get(&uuid(1))) > estimate_difficulty(medium, None));
+    }
+
+    #[test]
+    fn adaptive_quiz_survives_restart() {
+
+        let schema = fixtures::sample_scheme();
+        let mut storage = Storage::open_in_memory().unwrap();
+        let student = storage.create_student("Тест", None).unwrap();
+        let session = storage.start_session(student.id).unwrap();
+        storage.record_adaptive_start(session.id, 4, "Основной", "1").unwrap();
+
+        let mut sessions = AdaptiveSessions::default();
+        let mut step = sessions.start(session.id, &schema, &HashMap::new(), 4).unwrap();
+        for _ in 0..2 {
+            let question = step.question.unwrap();
+            let answer = SubmittedAnswer { question_uuid: question.uuid, answers: vec![question.variants[0].id.clone()] };
+            step = sessions.answer(session.id, &schema, answer).unwrap();
+            storage.record_quiz_answers(session.id, &[step.last.as_ref().unwrap().to_record()]).unwrap();
+        }
+
+        // Перезапуск: в памяти ничего нет, длина теста и ответы остались в базе
+        let session = storage.get_session(session.id).unwrap();
+        let mut restarted = AdaptiveSessions::default();
+        restarted.restore(&session, &schema, &storage.question_statistics().unwrap(), &storage.session_quiz_answers(session.id).unwrap());
+
+        let resumed = restarted.current(session.id, &schema).unwrap();
+        assert_eq!(resumed.answered, 2);
+        assert_eq!(resumed.ability, step.ability);
+        assert_eq!(resumed.question.unwrap().uuid, step.question.unwrap().uuid);
+    }
+}
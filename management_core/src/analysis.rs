@@ -0,0 +1,328 @@
+use std::collections::HashMap;
+use std::io;
+use serde::{Deserialize, Serialize};
+
+use crate::{CoefficientScheme, QuizAnswerRecord};
+
+/// Доля сессий с лучшими и худшими результатами для индекса дискриминации
+const EXTREME_GROUP_SHARE: f64 = 0.27;
+
+/// Меньше ответов — выводы по вопросу ненадёжны
+pub const MIN_ATTEMPTS_FOR_ANALYSIS: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemFlag {
+    /// Верно отвечают больше 90%
+    TooEasy,
+    /// Верно отвечают меньше 20%
+    TooHard,
+    /// Сильные и слабые студенты отвечают почти одинаково
+    LowDiscrimination,
+    /// Слабые студенты отвечают лучше сильных: возможно, ошибка в ключе
+    NegativeDiscrimination,
+    /// Неправильный вариант выбирают чаще, чем отвечают верно
+    MisleadingDistractor,
+    /// Неправильный вариант никто не выбирает
+    UnusedDistractor,
+    FewAttempts
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VariantChoice {
+    pub id: String,
+    pub content: String,
+    pub is_answer: bool,
+    pub chosen: usize,
+    /// Доля ответов с известным выбором, в которых выбран вариант
+    pub rate: f64
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ItemAnalysis {
+    pub question_uuid: String,
+    pub title: String,
+    pub attempts: usize,
+    /// Средняя доля балла, от 0 до 1
+    pub correct_rate: f64,
+    /// Разность correct_rate в верхней и нижней группах сессий, от -1 до 1.
+    /// `None`, если на вопрос не отвечали в одной из групп.
+    pub discrimination: Option<f64>,
+    pub variants: Vec<VariantChoice>,
+    pub flags: Vec<ItemFlag>
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ItemAnalysisReport {
+    /// Число сессий, в которых есть ответы на вопросы
+    pub sessions: usize,
+    pub items: Vec<ItemAnalysis>
+}
+
+impl CoefficientScheme {
+    /// Анализ вопросов банка по сохранённым ответам. Ответы на вопросы,
+    /// которых уже нет в схеме, не учитываются.
+    pub fn analyze_items(&self, answers: &[(i64, QuizAnswerRecord)]) -> ItemAnalysisReport {
+
+        // Итог сессии — взвешенная доля балла по всем её ответам
+        let mut totals: HashMap<i64, (f64, f64)> = HashMap::new();
+        for (session_id, answer) in answers {
+            let total = totals.entry(*session_id).or_default();
+            total.0 += answer.credit * answer.weight;
+            total.1 += answer.weight;
+        }
+
+        let mut ranked = totals
+            .iter()
+            .map(|(session_id, (points, weight))| (*session_id, if *weight == 0.0 { 0.0 } else { points / weight }))
+            .collect::<Vec<(i64, f64)>>();
+        ranked.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+
+        let group = ((ranked.len() as f64 * EXTREME_GROUP_SHARE).ceil() as usize).min(ranked.len() / 2);
+        let bottom = ranked[..group].iter().map(|(id, _)| *id).collect::<Vec<i64>>();
+        let top = ranked[ranked.len() - group..].iter().map(|(id, _)| *id).collect::<Vec<i64>>();
+
+        let mut items = self
+            .get_questions()
+            .iter()
+            .map(|question| {
+                let question_answers = answers
+                    .iter()
+                    .filter(|(_, answer)| &answer.question_uuid == question.get_uuid())
+                    .collect::<Vec<&(i64, QuizAnswerRecord)>>();
+
+                let attempts = question_answers.len();
+                let correct_rate = mean_credit(question_answers.iter().map(|(_, answer)| answer));
+
+                // Без ответов в обеих крайних группах сравнивать нечего
+                let group_rate = |group: &[i64]| {
+                    let mut group_answers = question_answers
+                        .iter()
+                        .filter(|(session_id, _)| group.contains(session_id))
+                        .map(|(_, answer)| answer)
+                        .peekable();
+
+                    group_answers.peek().is_some().then(|| mean_credit(group_answers))
+                };
+                let discrimination = group_rate(&top).zip(group_rate(&bottom)).map(|(top, bottom)| top - bottom);
+
+                let with_selection = question_answers
+                    .iter()
+                    .filter(|(_, answer)| !answer.selected.is_empty())
+                    .count();
+
+                let variants = question
+                    .get_variants()
+                    .iter()
+                    .map(|variant| {
+                        let chosen = question_answers
+                            .iter()
+                            .filter(|(_, answer)| answer.selected.contains(variant.get_id()))
+                            .count();
+
+                        VariantChoice {
+                            id: variant.get_id().clone(),
+                            content: variant.get_content().clone(),
+                            is_answer: variant.get_answer_state(),
+                            chosen,
+                            rate: if with_selection == 0 { 0.0 } else { chosen as f64 / with_selection as f64 }
+                        }
+                    })
+                    .collect::<Vec<VariantChoice>>();
+
+                let mut flags = vec![];
+                if attempts < MIN_ATTEMPTS_FOR_ANALYSIS {
+                    flags.push(ItemFlag::FewAttempts);
+                }
+                if attempts > 0 && correct_rate > 0.9 {
+                    flags.push(ItemFlag::TooEasy);
+                }
+                if attempts > 0 && correct_rate < 0.2 {
+                    flags.push(ItemFlag::TooHard);
+                }
+                match discrimination {
+                    Some(value) if value < 0.0 => flags.push(ItemFlag::NegativeDiscrimination),
+                    Some(value) if value < 0.2 => flags.push(ItemFlag::LowDiscrimination),
+                    _ => {}
+                }
+                if with_selection > 0 {
+                    let distractors = variants.iter().filter(|variant| !variant.is_answer);
+
+                    if distractors.clone().any(|variant| variant.rate > correct_rate) {
+                        flags.push(ItemFlag::MisleadingDistractor);
+                    }
+                    if with_selection >= MIN_ATTEMPTS_FOR_ANALYSIS && distractors.clone().any(|variant| variant.chosen == 0) {
+                        flags.push(ItemFlag::UnusedDistractor);
+                    }
+                }
+
+                ItemAnalysis {
+                    question_uuid: question.get_uuid().clone(),
+                    title: question.get_title().clone(),
+                    attempts,
+                    correct_rate,
+                    discrimination,
+                    variants,
+                    flags
+                }
+            })
+            .collect::<Vec<ItemAnalysis>>();
+
+        // Сначала вопросы, требующие внимания
+        items.sort_by(|a, b| b.flags.len().cmp(&a.flags.len()).then_with(|| a.title.cmp(&b.title)));
+
+        ItemAnalysisReport {
+            sessions: ranked.len(),
+            items
+        }
+    }
+}
+
+fn mean_credit<'a>(answers: impl Iterator<Item = &'a QuizAnswerRecord>) -> f64 {
+    let (sum, count) = answers.fold((0.0, 0), |(sum, count), answer| (sum + answer.credit, count + 1));
+
+    if count == 0 { 0.0 } else { sum / count as f64 }
+}
+
+impl ItemAnalysisReport {
+    /// Одна строка на вариант ответа: авторам удобно фильтровать таблицу
+    /// по вопросу и флагам
+    pub fn write_csv<W: io::Write>(&self, writer: W) -> io::Result<()> {
+        let mut csv = csv::Writer::from_writer(writer);
+
+        csv.write_record([
+            "question_uuid", "title", "attempts", "correct_rate", "discrimination", "flags",
+            "variant_id", "variant", "is_answer", "chosen", "chosen_rate"
+        ])?;
+
+        for item in &self.items {
+            let flags = item.flags
+                .iter()
+                .map(|flag| serde_json::to_value(flag).ok().and_then(|flag| flag.as_str().map(str::to_owned)))
+                .collect::<Option<Vec<String>>>()
+                .unwrap_or_default()
+                .join(" ");
+
+            for variant in &item.variants {
+                csv.write_record([
+                    item.question_uuid.clone(),
+                    item.title.clone(),
+                    item.attempts.to_string(),
+                    format!("{:.3}", item.correct_rate),
+                    item.discrimination.map_or(String::new(), |value| format!("{:.3}", value)),
+                    flags.clone(),
+                    variant.id.clone(),
+                    variant.content.clone(),
+                    variant.is_answer.to_string(),
+                    variant.chosen.to_string(),
+                    format!("{:.3}", variant.rate)
+                ])?;
+            }
+        }
+
+        csv.flush()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use crate::{fixtures, question_uuid};
+    use super::*;
+
+    fn answer(title: &str, pick: &str) -> QuizAnswerRecord {
+        QuizAnswerRecord {
+            question_uuid: question_uuid(title),
+            is_correct: pick == "right",
+            credit: if pick == "right" { 1.0 } else { 0.0 },
+            weight: 1.0,
+            selected: vec![pick.to_owned()]
+        }
+    }
+
+    /// Анализ четырёх вопросов по десяти сессиям. Сильные студенты (0–4)
+    /// решают «Сложный», но ошибаются в «Обманчивом», слабые (5–9) — наоборот.
+    fn report() -> ItemAnalysisReport {
+        let question = |title: &str| json!({
+            "title": title,
+            "variants": [
+                { "id": "right", "content": "Верно", "is_answer": true },
+                { "id": "trap", "content": "Ловушка", "is_answer": false },
+                { "id": "other", "content": "Другое", "is_answer": false }
+            ]
+        });
+        let schema = fixtures::scheme(json!({
+            "questions": [question("Лёгкий"), question("Обманчивый"), question("Сложный"), question("Забытый")]
+        }));
+
+        let mut answers = vec![];
+        for session_id in 0..10 {
+            let strong = session_id < 5;
+            answers.push((session_id, answer("Лёгкий", "right")));
+            answers.push((session_id, answer("Обманчивый", if strong { "trap" } else { "right" })));
+            answers.push((session_id, QuizAnswerRecord { weight: 3.0, ..answer("Сложный", if strong { "right" } else { "other" }) }));
+            answers.push((session_id, answer("Лишний", "right")));
+        }
+
+        schema.analyze_items(&answers)
+    }
+
+    fn item<'a>(report: &'a ItemAnalysisReport, title: &str) -> &'a ItemAnalysis {
+        report.items.iter().find(|item| item.title == title).unwrap()
+    }
+
+    #[test]
+    fn item_analysis_counts_sessions() {
+
+        assert_eq!(report().sessions, 10);
+    }
+
+    #[test]
+    fn always_solved_question_is_too_easy() {
+
+        assert!(item(&report(), "Лёгкий").flags.contains(&ItemFlag::TooEasy));
+    }
+
+    #[test]
+    fn misleading_question_has_negative_discrimination() {
+
+        let report = report();
+        let misleading = item(&report, "Обманчивый");
+
+        assert_eq!(misleading.correct_rate, 0.5);
+        assert_eq!(misleading.discrimination, Some(-1.0));
+        assert!(misleading.flags.contains(&ItemFlag::NegativeDiscrimination));
+    }
+
+    #[test]
+    fn never_chosen_variant_is_unused_distractor() {
+
+        let report = report();
+        let misleading = item(&report, "Обманчивый");
+
+        assert_eq!(misleading.variants[1].chosen, 5);
+        assert!(misleading.flags.contains(&ItemFlag::UnusedDistractor));
+    }
+
+    #[test]
+    fn unanswered_question_has_no_discrimination() {
+
+        let report = report();
+        let unanswered = item(&report, "Забытый");
+
+        assert_eq!(unanswered.attempts, 0);
+        assert_eq!(unanswered.discrimination, None);
+        assert!(!unanswered.flags.contains(&ItemFlag::LowDiscrimination));
+    }
+
+    #[test]
+    fn item_analysis_csv_has_row_per_variant() {
+
+        let mut csv = vec![];
+        report().write_csv(&mut csv).unwrap();
+
+        assert_eq!(String::from_utf8(csv).unwrap().lines().count(), 1 + 4 * 3);
+    }
+}
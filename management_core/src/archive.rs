@@ -0,0 +1,478 @@
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io::{self, Read, Seek, Write};
+use std::path::{Component, Path};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+use zip::result::ZipError;
+use zip::write::FileOptions;
+
+use crate::packs::sha256_hex;
+use crate::{CoefficientScheme, SchemaError, SchemaPack, ToPayload, LATEST_SCHEMA_VERSION, PACK_EXTENSION};
+
+/// Версия формата архива пакета
+pub const ARCHIVE_FORMAT: u32 = 1;
+
+pub const ARCHIVE_EXTENSION: &str = "zip";
+
+const MANIFEST_ENTRY: &str = "manifest.json";
+const SCHEMA_ENTRY: &str = "schema.json";
+const ASSETS_PREFIX: &str = "assets/";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssetEntry {
+    /// Имя файла внутри `assets/`
+    pub name: String,
+    pub checksum: String
+}
+
+/// `manifest.json` архива пакета
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackManifest {
+    pub format: u32,
+    pub name: String,
+    /// Версия пакета, как в [`SchemaPack::get_version`]
+    pub version: String,
+    pub schema_version: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    /// SHA-256 файла `schema.json` архива
+    pub checksum: String,
+    #[serde(default)]
+    pub assets: Vec<AssetEntry>
+}
+
+#[derive(Debug)]
+pub enum ArchiveError {
+    Io(io::Error),
+    Zip(ZipError),
+    MissingEntry(String),
+    UnsupportedFormat(u32),
+    /// Версия схемы в манифесте или в `schema.json` не та, что пишет экспорт
+    UnsupportedSchemaVersion(u32),
+    /// Имя пакета или файла в архиве не годится для имени файла
+    InvalidName(String),
+    ChecksumMismatch {
+        entry: String,
+        expected: String,
+        found: String
+    },
+    /// Версия пакета в манифесте не совпадает с отпечатком схемы
+    VersionMismatch {
+        expected: String,
+        found: String
+    },
+    /// Файл в `assets/` указан в манифесте дважды
+    DuplicateAsset(String),
+    /// Пакет с таким именем уже установлен
+    PackExists(String),
+    Manifest(serde_json::Error),
+    Schema(SchemaError)
+}
+
+impl From<io::Error> for ArchiveError {
+    fn from(value: io::Error) -> Self {
+        ArchiveError::Io(value)
+    }
+}
+
+impl From<ZipError> for ArchiveError {
+    fn from(value: ZipError) -> Self {
+        match value {
+            ZipError::Io(err) => ArchiveError::Io(err),
+            err => ArchiveError::Zip(err)
+        }
+    }
+}
+
+impl From<SchemaError> for ArchiveError {
+    fn from(value: SchemaError) -> Self {
+        ArchiveError::Schema(value)
+    }
+}
+
+impl Display for ArchiveError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArchiveError::Io(err) => write!(f, "Ошибка ввода-вывода архива: {}", err),
+            ArchiveError::Zip(err) => write!(f, "Повреждённый архив: {}", err),
+            ArchiveError::MissingEntry(entry) => write!(f, "В архиве нет файла {:?}", entry),
+            ArchiveError::UnsupportedFormat(format) =>
+                write!(f, "Формат архива {} не поддерживается, ожидался {}", format, ARCHIVE_FORMAT),
+            ArchiveError::UnsupportedSchemaVersion(version) =>
+                write!(f, "Версия схемы {} в архиве не поддерживается, ожидалась {}", version, LATEST_SCHEMA_VERSION),
+            ArchiveError::InvalidName(name) => write!(f, "Недопустимое имя {:?} в архиве", name),
+            ArchiveError::ChecksumMismatch { entry, .. } =>
+                write!(f, "Контрольная сумма {:?} не совпадает с манифестом", entry),
+            ArchiveError::VersionMismatch { expected, found } =>
+                write!(f, "Версия пакета {} в манифесте не совпадает с версией схемы {}", expected, found),
+            ArchiveError::DuplicateAsset(name) => write!(f, "Файл {:?} повторяется в манифесте", name),
+            ArchiveError::PackExists(name) => write!(f, "Пакет {:?} уже установлен", name),
+            ArchiveError::Manifest(err) => write!(f, "Некорректный {}: {}", MANIFEST_ENTRY, err),
+            ArchiveError::Schema(err) => write!(f, "{}", err)
+        }
+    }
+}
+
+impl std::error::Error for ArchiveError {}
+
+impl ToPayload for ArchiveError {
+    fn code_and_details(&self) -> (&'static str, Value) {
+        match self {
+            ArchiveError::Io(err) => ("archive_io", json!({ "kind": format!("{:?}", err.kind()) })),
+            ArchiveError::Zip(_) => ("archive_corrupted", Value::Null),
+            ArchiveError::MissingEntry(entry) => ("archive_missing_entry", json!({ "entry": entry })),
+            ArchiveError::UnsupportedFormat(format) =>
+                ("archive_unsupported_format", json!({ "format": format, "supported": ARCHIVE_FORMAT })),
+            ArchiveError::UnsupportedSchemaVersion(version) =>
+                ("archive_unsupported_schema_version", json!({ "version": version, "supported": LATEST_SCHEMA_VERSION })),
+            ArchiveError::InvalidName(name) => ("archive_invalid_name", json!({ "name": name })),
+            ArchiveError::ChecksumMismatch { entry, expected, found } =>
+                ("archive_checksum_mismatch", json!({ "entry": entry, "expected": expected, "found": found })),
+            ArchiveError::VersionMismatch { expected, found } =>
+                ("archive_version_mismatch", json!({ "expected": expected, "found": found })),
+            ArchiveError::DuplicateAsset(name) => ("archive_duplicate_asset", json!({ "name": name })),
+            ArchiveError::PackExists(name) => ("archive_pack_exists", json!({ "name": name })),
+            ArchiveError::Manifest(err) =>
+                ("archive_manifest", json!({ "line": err.line(), "column": err.column() })),
+            ArchiveError::Schema(err) => err.code_and_details()
+        }
+    }
+}
+
+/// Имя из одного обычного компонента пути: без каталогов и `..`
+fn is_plain_name(name: &str) -> bool {
+    let mut components = Path::new(name).components();
+
+    matches!((components.next(), components.next()), (Some(Component::Normal(_)), None))
+}
+
+fn read_entry<R: Read + Seek>(archive: &mut ZipArchive<R>, name: &str) -> Result<Vec<u8>, ArchiveError> {
+    let mut entry = archive.by_name(name).map_err(|err| match err {
+        ZipError::FileNotFound => ArchiveError::MissingEntry(name.to_owned()),
+        err => err.into()
+    })?;
+
+    let mut bytes = vec![];
+    entry.read_to_end(&mut bytes)?;
+
+    Ok(bytes)
+}
+
+fn verify_checksum(entry: &str, bytes: &[u8], expected: &str) -> Result<(), ArchiveError> {
+    let found = sha256_hex(bytes);
+
+    if found != expected {
+        return Err(ArchiveError::ChecksumMismatch {
+            entry: entry.to_owned(),
+            expected: expected.to_owned(),
+            found
+        });
+    }
+
+    Ok(())
+}
+
+impl SchemaPack {
+    /// Записывает пакет в zip-архив: `manifest.json`, `schema.json` и файлы
+    /// из [`SchemaPack::get_assets_dir`] в `assets/`
+    pub fn export_archive<W: Write + Seek>(&self, writer: W, author: Option<String>) -> Result<PackManifest, ArchiveError> {
+
+        let schema = serde_json::to_vec_pretty(&self.get_schema().to_document()).map_err(SchemaError::from)?;
+
+        let mut assets = vec![];
+        let assets_dir = self.get_assets_dir();
+        if assets_dir.is_dir() {
+            for entry in fs::read_dir(&assets_dir)? {
+                let path = entry?.path();
+                if !path.is_file() {
+                    continue
+                }
+
+                let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+                assets.push((name, fs::read(&path)?));
+            }
+        }
+        assets.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let manifest = PackManifest {
+            format: ARCHIVE_FORMAT,
+            name: self.get_name().clone(),
+            version: self.get_version().clone(),
+            schema_version: LATEST_SCHEMA_VERSION,
+            author,
+            checksum: sha256_hex(&schema),
+            assets: assets
+                .iter()
+                .map(|(name, bytes)| AssetEntry { name: name.clone(), checksum: sha256_hex(bytes) })
+                .collect()
+        };
+
+        let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+        let mut zip = ZipWriter::new(writer);
+
+        zip.start_file(MANIFEST_ENTRY, options)?;
+        zip.write_all(&serde_json::to_vec_pretty(&manifest).map_err(ArchiveError::Manifest)?)?;
+
+        zip.start_file(SCHEMA_ENTRY, options)?;
+        zip.write_all(&schema)?;
+
+        for (name, bytes) in &assets {
+            zip.start_file(format!("{}{}", ASSETS_PREFIX, name), options)?;
+            zip.write_all(bytes)?;
+        }
+
+        zip.finish()?;
+
+        Ok(manifest)
+    }
+}
+
+/// Архив пакета, прочитанный и проверенный, но ещё не установленный
+#[derive(Debug, Clone)]
+pub struct PackArchive {
+    pub manifest: PackManifest,
+    schema: CoefficientScheme,
+    schema_bytes: Vec<u8>,
+    assets: Vec<(String, Vec<u8>)>
+}
+
+impl PackArchive {
+    /// Читает архив и проверяет манифест, контрольные суммы и схему.
+    /// Схема с ошибками валидации, другой версии формата или с версией
+    /// пакета, не совпадающей с манифестом, отклоняется.
+    pub fn read<R: Read + Seek>(reader: R) -> Result<Self, ArchiveError> {
+
+        let mut archive = ZipArchive::new(reader)?;
+
+        let manifest: PackManifest = serde_json::from_slice(&read_entry(&mut archive, MANIFEST_ENTRY)?)
+            .map_err(ArchiveError::Manifest)?;
+
+        if manifest.format != ARCHIVE_FORMAT {
+            return Err(ArchiveError::UnsupportedFormat(manifest.format));
+        }
+        if manifest.schema_version != LATEST_SCHEMA_VERSION {
+            return Err(ArchiveError::UnsupportedSchemaVersion(manifest.schema_version));
+        }
+        if !is_plain_name(&manifest.name) {
+            return Err(ArchiveError::InvalidName(manifest.name));
+        }
+
+        let schema_bytes = read_entry(&mut archive, SCHEMA_ENTRY)?;
+        verify_checksum(SCHEMA_ENTRY, &schema_bytes, &manifest.checksum)?;
+
+        let schema = CoefficientScheme::from_value(&serde_json::from_slice(&schema_bytes).map_err(SchemaError::from)?)?;
+        if schema.get_source_version() != manifest.schema_version {
+            return Err(ArchiveError::UnsupportedSchemaVersion(schema.get_source_version()));
+        }
+        let report = schema.validate();
+        if report.has_errors() {
+            return Err(SchemaError::Invalid(report).into());
+        }
+
+        let version = schema.pack_version();
+        if version != manifest.version {
+            return Err(ArchiveError::VersionMismatch { expected: manifest.version, found: version });
+        }
+
+        let mut assets: Vec<(String, Vec<u8>)> = vec![];
+        for asset in &manifest.assets {
+            if !is_plain_name(&asset.name) {
+                return Err(ArchiveError::InvalidName(asset.name.clone()));
+            }
+            if assets.iter().any(|(name, _)| name == &asset.name) {
+                return Err(ArchiveError::DuplicateAsset(asset.name.clone()));
+            }
+
+            let entry = format!("{}{}", ASSETS_PREFIX, asset.name);
+            let bytes = read_entry(&mut archive, &entry)?;
+            verify_checksum(&entry, &bytes, &asset.checksum)?;
+
+            assets.push((asset.name.clone(), bytes));
+        }
+
+        Ok(Self { manifest, schema, schema_bytes, assets })
+    }
+
+    /// Устанавливает пакет в каталог пакетов. Существующий пакет с тем же
+    /// именем не перезаписывается.
+    pub fn install(self, dir: &Path) -> Result<SchemaPack, ArchiveError> {
+
+        let path = dir.join(format!("{}.{}", self.manifest.name, PACK_EXTENSION));
+        if path.exists() {
+            return Err(ArchiveError::PackExists(self.manifest.name));
+        }
+
+        fs::create_dir_all(dir)?;
+        let pack = SchemaPack::new(self.manifest.name, path, self.schema);
+
+        if !self.assets.is_empty() {
+            let assets_dir = pack.get_assets_dir();
+            fs::create_dir_all(&assets_dir)?;
+
+            for (name, bytes) in &self.assets {
+                fs::write(assets_dir.join(name), bytes)?;
+            }
+        }
+
+        // Схему пишем последней и через переименование: наблюдатель за
+        // каталогом пакетов увидит её целиком и уже с файлами пакета
+        let tmp_path = pack.get_path().with_extension("json.tmp");
+        fs::write(&tmp_path, &self.schema_bytes)?;
+        fs::rename(&tmp_path, pack.get_path())?;
+
+        Ok(pack)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use crate::fixtures;
+    use super::*;
+
+    /// Пакет с одним ресурсом во временном каталоге `archive_<name>_<pid>`
+    /// и его архив
+    fn exported(name: &str) -> (PathBuf, SchemaPack, PackManifest, Vec<u8>) {
+        let dir = std::env::temp_dir().join(format!("archive_{}_{}", name, std::process::id()));
+        fs::create_dir_all(dir.join("it.assets")).unwrap();
+        fs::copy(fixtures::SAMPLE_SCHEMA, dir.join("it.json")).unwrap();
+        fs::write(dir.join("it.assets").join("logo.svg"), "<svg/>").unwrap();
+
+        let pack = SchemaPack::load(&dir.join("it.json")).unwrap();
+        let mut archive = io::Cursor::new(vec![]);
+        let manifest = pack.export_archive(&mut archive, Some("Кафедра ИТ".into())).unwrap();
+
+        (dir, pack, manifest, archive.into_inner())
+    }
+
+    /// Тот же архив с другим манифестом
+    fn with_manifest(archive: &[u8], manifest: &PackManifest) -> io::Cursor<Vec<u8>> {
+        let mut source = zip::ZipArchive::new(io::Cursor::new(archive)).unwrap();
+        let mut repacked = zip::ZipWriter::new(io::Cursor::new(vec![]));
+        repacked.start_file("manifest.json", zip::write::FileOptions::default()).unwrap();
+        repacked.write_all(&serde_json::to_vec(manifest).unwrap()).unwrap();
+        for index in 0..source.len() {
+            let entry = source.by_index(index).unwrap();
+            if entry.name() != "manifest.json" {
+                repacked.raw_copy_file(entry).unwrap();
+            }
+        }
+        io::Cursor::new(repacked.finish().unwrap().into_inner())
+    }
+
+    #[test]
+    fn exported_archive_carries_manifest() {
+
+        let (dir, _, manifest, archive) = exported("manifest");
+        assert_eq!(manifest.assets.len(), 1);
+
+        let imported = PackArchive::read(io::Cursor::new(archive)).unwrap();
+        assert_eq!(imported.manifest.author.as_deref(), Some("Кафедра ИТ"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn install_refuses_existing_pack() {
+
+        let (dir, _, _, archive) = exported("exists");
+
+        let imported = PackArchive::read(io::Cursor::new(archive)).unwrap();
+        assert!(matches!(imported.install(&dir), Err(ArchiveError::PackExists(_))));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn installed_pack_keeps_version_and_assets() {
+
+        let (dir, pack, _, archive) = exported("install");
+
+        let installed = PackArchive::read(io::Cursor::new(archive)).unwrap().install(&dir.join("installed")).unwrap();
+        assert_eq!(installed.get_version(), pack.get_version());
+        assert_eq!(SchemaPack::load(installed.get_path()).unwrap().get_version(), pack.get_version());
+        assert_eq!(fs::read_to_string(installed.get_assets_dir().join("logo.svg")).unwrap(), "<svg/>");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn tampered_schema_fails_checksum() {
+
+        let (dir, _, manifest, _) = exported("tampered");
+
+        // Подмена схемы после экспорта
+        let options = zip::write::FileOptions::default();
+        let mut tampered = zip::ZipWriter::new(io::Cursor::new(vec![]));
+        tampered.start_file("manifest.json", options).unwrap();
+        tampered.write_all(&serde_json::to_vec(&manifest).unwrap()).unwrap();
+        tampered.start_file("schema.json", options).unwrap();
+        tampered.write_all(b"{}").unwrap();
+        let tampered = tampered.finish().unwrap();
+        assert!(matches!(
+            PackArchive::read(io::Cursor::new(tampered.into_inner())),
+            Err(ArchiveError::ChecksumMismatch { .. })
+        ));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn pack_name_cannot_escape_packs_dir() {
+
+        let (dir, _, manifest, _) = exported("escaping");
+
+        let mut escaping = zip::ZipWriter::new(io::Cursor::new(vec![]));
+        escaping.start_file("manifest.json", zip::write::FileOptions::default()).unwrap();
+        escaping.write_all(&serde_json::to_vec(&PackManifest { name: "../it".into(), ..manifest }).unwrap()).unwrap();
+        let escaping = escaping.finish().unwrap();
+        assert!(matches!(
+            PackArchive::read(io::Cursor::new(escaping.into_inner())),
+            Err(ArchiveError::InvalidName(_))
+        ));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn manifest_version_must_match_schema() {
+
+        let (dir, _, manifest, archive) = exported("version");
+
+        assert!(matches!(
+            PackArchive::read(with_manifest(&archive, &PackManifest { version: "0".repeat(16), ..manifest })),
+            Err(ArchiveError::VersionMismatch { .. })
+        ));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn newer_schema_version_is_unsupported() {
+
+        let (dir, _, manifest, archive) = exported("schema_version");
+
+        assert!(matches!(
+            PackArchive::read(with_manifest(&archive, &PackManifest { schema_version: LATEST_SCHEMA_VERSION + 1, ..manifest })),
+            Err(ArchiveError::UnsupportedSchemaVersion(_))
+        ));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn duplicate_asset_is_rejected() {
+
+        let (dir, _, manifest, archive) = exported("duplicate");
+
+        let assets = vec![manifest.assets[0].clone(), manifest.assets[0].clone()];
+        assert!(matches!(
+            PackArchive::read(with_manifest(&archive, &PackManifest { assets, ..manifest })),
+            Err(ArchiveError::DuplicateAsset(_))
+        ));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
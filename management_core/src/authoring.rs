@@ -0,0 +1,560 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+use serde_json::{json, Value};
+
+use crate::document::{backup_schema_file, source_pointer, write_schema_file};
+use crate::{
+    question_uuid, AnswerVariantDocument, CoefficientScheme, JobLevel, PositionDocument, Question,
+    QuestionDocumentV2, SchemaDocument, SchemaError, Severity, ToPayload, ValidationReport, VacancyDocument, LATEST_SCHEMA_VERSION
+};
+
+#[derive(Debug)]
+pub enum AuthoringError {
+    NotFound {
+        entity: &'static str,
+        name: String
+    },
+    AlreadyExists {
+        entity: &'static str,
+        name: String
+    },
+    /// Вакансию используют навыки или должности компаний. Должности нужно
+    /// перевести на другую вакансию вручную, коэффициенты навыков можно
+    /// удалить вместе с вакансией
+    VacancyInUse {
+        vacancy: String,
+        skills: Vec<String>,
+        /// «компания/должность»
+        positions: Vec<String>
+    },
+    /// После правки схема не проходит валидацию, правка не применена
+    Invalid(ValidationReport),
+    Schema(SchemaError)
+}
+
+impl From<SchemaError> for AuthoringError {
+    fn from(value: SchemaError) -> Self {
+        AuthoringError::Schema(value)
+    }
+}
+
+impl Display for AuthoringError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuthoringError::NotFound { entity, name } => write!(f, "{} {:?} отсутствует в схеме", entity, name),
+            AuthoringError::AlreadyExists { entity, name } => write!(f, "{} {:?} уже есть в схеме", entity, name),
+            AuthoringError::VacancyInUse { vacancy, skills, positions } => write!(
+                f,
+                "Вакансия {:?} используется: навыков {}, должностей {}",
+                vacancy, skills.len(), positions.len()
+            ),
+            AuthoringError::Invalid(report) => write!(f, "Правка нарушает схему. {}", report),
+            AuthoringError::Schema(err) => write!(f, "{}", err)
+        }
+    }
+}
+
+impl std::error::Error for AuthoringError {}
+
+impl ToPayload for AuthoringError {
+    fn code_and_details(&self) -> (&'static str, Value) {
+        match self {
+            AuthoringError::NotFound { entity, name } =>
+                ("authoring_not_found", json!({ "entity": entity, "name": name })),
+            AuthoringError::AlreadyExists { entity, name } =>
+                ("authoring_already_exists", json!({ "entity": entity, "name": name })),
+            AuthoringError::VacancyInUse { vacancy, skills, positions } =>
+                ("authoring_vacancy_in_use", json!({
+                    "vacancy": vacancy,
+                    "skills": skills,
+                    "positions": positions
+                })),
+            AuthoringError::Invalid(report) => ("authoring_invalid", json!(report)),
+            AuthoringError::Schema(err) => err.code_and_details()
+        }
+    }
+}
+
+fn position_document(level: &JobLevel) -> PositionDocument {
+    let (position, vacancy) = level
+        .label()
+        .iter()
+        .next()
+        .map(|(position, vacancy)| (position.clone(), vacancy.clone()))
+        .unwrap_or_default();
+
+    PositionDocument {
+        position,
+        vacancy,
+        children: level.children.iter().flatten().map(position_document).collect()
+    }
+}
+
+fn question_document(question: &Question) -> QuestionDocumentV2 {
+    QuestionDocumentV2 {
+        uuid: Some(question.uuid.clone()),
+        title: question.title.clone(),
+        category: question.category.clone(),
+        weight: question.weight,
+        difficulty: question.difficulty,
+        grading: question.grading,
+        variants: question.variants
+            .iter()
+            .map(|variant| AnswerVariantDocument {
+                id: Some(variant.id.clone()),
+                content: variant.content.clone(),
+                is_answer: variant.is_answer
+            })
+            .collect()
+    }
+}
+
+fn positions_with_vacancy(position: &PositionDocument, vacancy: &str, company: &str, found: &mut Vec<String>) {
+    if position.vacancy == vacancy {
+        found.push(format!("{}/{}", company, position.position));
+    }
+
+    for child in &position.children {
+        positions_with_vacancy(child, vacancy, company, found);
+    }
+}
+
+fn rename_position_vacancy(position: &mut PositionDocument, vacancy: &str, new_name: &str) {
+    if position.vacancy == vacancy {
+        position.vacancy = new_name.to_owned();
+    }
+
+    for child in &mut position.children {
+        rename_position_vacancy(child, vacancy, new_name);
+    }
+}
+
+fn question_index(document: &SchemaDocument, uuid: &str) -> Result<usize, AuthoringError> {
+    document.questions
+        .iter()
+        .position(|question| question.uuid.as_deref() == Some(uuid))
+        .ok_or_else(|| AuthoringError::NotFound { entity: "Вопрос", name: uuid.to_owned() })
+}
+
+impl CoefficientScheme {
+    /// Схема в актуальном формате файла. Вопросы упорядочены по заголовку,
+    /// у вопросов и вариантов всегда записаны идентификаторы.
+    pub fn to_document(&self) -> SchemaDocument {
+
+        let vacancies = self.vacancies
+            .iter()
+            .map(|(vacancy_id, vacancy)| VacancyDocument {
+                name: vacancy.0.clone(),
+                description: self.vacancies.get_description(vacancy_id).cloned()
+            })
+            .collect();
+
+        let skills = self.skills
+            .iter()
+            .map(|skill| {
+                let view = self.get_skill_view(skill);
+                let coefficients = view.vacancies_coefficient
+                    .into_iter()
+                    .map(|vac_coef| (vac_coef.vacancy.0, vac_coef.coefficient))
+                    .collect::<BTreeMap<String, i64>>();
+
+                (view.name, coefficients)
+            })
+            .collect();
+
+        let companies = self.companies
+            .iter()
+            .map(|company| (company.name.clone(), position_document(company.tree())))
+            .collect();
+
+        let mut questions = self.questions.iter().map(question_document).collect::<Vec<QuestionDocumentV2>>();
+        questions.sort_by(|a, b| a.title.cmp(&b.title));
+
+        SchemaDocument {
+            schema_version: LATEST_SCHEMA_VERSION,
+            vacancies,
+            skills,
+            companies,
+            questions
+        }
+    }
+
+    /// Записывает схему в файл в актуальной версии формата. Файл старой
+    /// версии перед первой записью копируется в `*.v1.json.bak`.
+    pub fn save(&mut self, path: &Path) -> Result<(), SchemaError> {
+
+        if self.source_version < LATEST_SCHEMA_VERSION && path.exists() {
+            backup_schema_file(path, self.source_version)?;
+        }
+
+        write_schema_file(path, &self.to_document())?;
+        self.source_version = LATEST_SCHEMA_VERSION;
+
+        Ok(())
+    }
+
+    /// Правит документ схемы и разбирает его заново. Если правка добавила
+    /// ошибки валидации, текущая схема не меняется. Ошибки, которые были
+    /// в схеме до правки, её не блокируют и в отчёт не попадают.
+    pub(crate) fn edit<T>(
+        &mut self,
+        edit: impl FnOnce(&mut SchemaDocument) -> Result<T, AuthoringError>
+    ) -> Result<T, AuthoringError> {
+
+        let before = self.validate_latest();
+        let existing = before
+            .errors()
+            .map(|diagnostic| (diagnostic.code, diagnostic.pointer.as_str()))
+            .collect::<HashSet<(&str, &str)>>();
+
+        let mut document = self.to_document();
+        let result = edit(&mut document)?;
+
+        let json = serde_json::to_value(&document).map_err(SchemaError::from)?;
+        let mut scheme = CoefficientScheme::from_value(&json)?;
+
+        let mut report = scheme.validate_latest();
+        report.diagnostics.retain(|diagnostic| {
+            diagnostic.severity != Severity::Error || !existing.contains(&(diagnostic.code, diagnostic.pointer.as_str()))
+        });
+        if report.has_errors() {
+            for diagnostic in &mut report.diagnostics {
+                diagnostic.pointer = source_pointer(self.source_version, &diagnostic.pointer);
+            }
+            return Err(AuthoringError::Invalid(report));
+        }
+
+        scheme.source_version = self.source_version;
+        *self = scheme;
+
+        Ok(result)
+    }
+
+    pub fn add_vacancy(&mut self, name: String, description: Option<String>) -> Result<(), AuthoringError> {
+        self.edit(|document| {
+            if document.vacancies.iter().any(|vacancy| vacancy.name == name) {
+                return Err(AuthoringError::AlreadyExists { entity: "Вакансия", name });
+            }
+
+            document.vacancies.push(VacancyDocument { name, description });
+
+            Ok(())
+        })
+    }
+
+    /// Переименовывает вакансию вместе с коэффициентами навыков и
+    /// должностями компаний, которые на неё ссылаются
+    pub fn update_vacancy(
+        &mut self,
+        name: &str,
+        new_name: String,
+        description: Option<String>
+    ) -> Result<(), AuthoringError> {
+        self.edit(|document| {
+            if new_name != name && document.vacancies.iter().any(|vacancy| vacancy.name == new_name) {
+                return Err(AuthoringError::AlreadyExists { entity: "Вакансия", name: new_name });
+            }
+
+            let vacancy = document.vacancies
+                .iter_mut()
+                .find(|vacancy| vacancy.name == name)
+                .ok_or_else(|| AuthoringError::NotFound { entity: "Вакансия", name: name.to_owned() })?;
+            vacancy.name = new_name.clone();
+            vacancy.description = description;
+
+            for coefficients in document.skills.values_mut() {
+                if let Some(coefficient) = coefficients.remove(name) {
+                    coefficients.insert(new_name.clone(), coefficient);
+                }
+            }
+
+            for tree in document.companies.values_mut() {
+                rename_position_vacancy(tree, name, &new_name);
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Удаляет вакансию. Если на неё ссылаются навыки, удаление
+    /// отклоняется, а с `cascade` их коэффициенты удаляются вместе с ней.
+    /// Должности компаний с этой вакансией удаление отклоняют всегда.
+    pub fn remove_vacancy(&mut self, name: &str, cascade: bool) -> Result<(), AuthoringError> {
+        self.edit(|document| {
+            let index = document.vacancies
+                .iter()
+                .position(|vacancy| vacancy.name == name)
+                .ok_or_else(|| AuthoringError::NotFound { entity: "Вакансия", name: name.to_owned() })?;
+
+            let skills = document.skills
+                .iter()
+                .filter(|(_, coefficients)| coefficients.contains_key(name))
+                .map(|(skill, _)| skill.clone())
+                .collect::<Vec<String>>();
+
+            let mut positions = vec![];
+            for (company, tree) in &document.companies {
+                positions_with_vacancy(tree, name, company, &mut positions);
+            }
+
+            if !positions.is_empty() || (!skills.is_empty() && !cascade) {
+                return Err(AuthoringError::VacancyInUse { vacancy: name.to_owned(), skills, positions });
+            }
+
+            for coefficients in document.skills.values_mut() {
+                coefficients.remove(name);
+            }
+            document.vacancies.remove(index);
+
+            Ok(())
+        })
+    }
+
+    /// Добавляет навык или заменяет все его коэффициенты
+    pub fn set_skill(&mut self, name: String, coefficients: BTreeMap<String, i64>) -> Result<(), AuthoringError> {
+        self.edit(|document| {
+            document.skills.insert(name, coefficients);
+
+            Ok(())
+        })
+    }
+
+    pub fn rename_skill(&mut self, name: &str, new_name: String) -> Result<(), AuthoringError> {
+        self.edit(|document| {
+            if new_name != name && document.skills.contains_key(&new_name) {
+                return Err(AuthoringError::AlreadyExists { entity: "Навык", name: new_name });
+            }
+
+            let coefficients = document.skills
+                .remove(name)
+                .ok_or_else(|| AuthoringError::NotFound { entity: "Навык", name: name.to_owned() })?;
+            document.skills.insert(new_name, coefficients);
+
+            Ok(())
+        })
+    }
+
+    pub fn remove_skill(&mut self, name: &str) -> Result<(), AuthoringError> {
+        self.edit(|document| {
+            document.skills
+                .remove(name)
+                .ok_or_else(|| AuthoringError::NotFound { entity: "Навык", name: name.to_owned() })?;
+
+            Ok(())
+        })
+    }
+
+    /// Добавляет компанию или заменяет её дерево должностей
+    pub fn set_company(&mut self, name: String, tree: PositionDocument) -> Result<(), AuthoringError> {
+        self.edit(|document| {
+            document.companies.insert(name, tree);
+
+            Ok(())
+        })
+    }
+
+    pub fn rename_company(&mut self, name: &str, new_name: String) -> Result<(), AuthoringError> {
+        self.edit(|document| {
+            if new_name != name && document.companies.contains_key(&new_name) {
+                return Err(AuthoringError::AlreadyExists { entity: "Компания", name: new_name });
+            }
+
+            let tree = document.companies
+                .remove(name)
+                .ok_or_else(|| AuthoringError::NotFound { entity: "Компания", name: name.to_owned() })?;
+            document.companies.insert(new_name, tree);
+
+            Ok(())
+        })
+    }
+
+    pub fn remove_company(&mut self, name: &str) -> Result<(), AuthoringError> {
+        self.edit(|document| {
+            document.companies
+                .remove(name)
+                .ok_or_else(|| AuthoringError::NotFound { entity: "Компания", name: name.to_owned() })?;
+
+            Ok(())
+        })
+    }
+
+    /// Добавляет вопрос и возвращает его uuid. Без явного uuid он
+    /// вычисляется по заголовку, как при разборе файла.
+    pub fn add_question(&mut self, mut question: QuestionDocumentV2) -> Result<String, AuthoringError> {
+        self.edit(|document| {
+            let uuid = question.uuid.clone().unwrap_or_else(|| question_uuid(&question.title));
+
+            if question_index(document, &uuid).is_ok() {
+                return Err(AuthoringError::AlreadyExists { entity: "Вопрос", name: uuid });
+            }
+
+            question.uuid = Some(uuid.clone());
+            document.questions.push(question);
+
+            Ok(uuid)
+        })
+    }
+
+    /// Заменяет вопрос. uuid сохраняется и при смене заголовка, поэтому
+    /// сохранённые ответы остаются связаны с вопросом.
+    pub fn update_question(&mut self, uuid: &str, mut question: QuestionDocumentV2) -> Result<(), AuthoringError> {
+        self.edit(|document| {
+            let index = question_index(document, uuid)?;
+
+            question.uuid = Some(uuid.to_owned());
+            document.questions[index] = question;
+
+            Ok(())
+        })
+    }
+
+    pub fn remove_question(&mut self, uuid: &str) -> Result<(), AuthoringError> {
+        self.edit(|document| {
+            let index = question_index(document, uuid)?;
+            document.questions.remove(index);
+
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{self, File};
+    use crate::fixtures;
+    use super::*;
+
+    fn schema() -> CoefficientScheme {
+        fixtures::scheme(json!({
+            "vacancies": [{ "name": "Team_Lead" }, { "name": "Janitor" }],
+            "skills": { "Ответственность": { "Team_Lead": 3, "Janitor": 1 } },
+            "companies": {
+                "Фирма": {
+                    "position": "Директор",
+                    "vacancy": "Team_Lead",
+                    "children": [{ "position": "Уборщик", "vacancy": "Janitor" }]
+                }
+            }
+        }))
+    }
+
+    fn question(title: &str) -> QuestionDocumentV2 {
+        QuestionDocumentV2 {
+            uuid: None,
+            title: title.into(),
+            category: None,
+            weight: None,
+            difficulty: Some(2),
+            grading: None,
+            variants: vec![
+                AnswerVariantDocument { id: None, content: "Да".into(), is_answer: true },
+                AnswerVariantDocument { id: None, content: "Нет".into(), is_answer: false }
+            ]
+        }
+    }
+
+    #[test]
+    fn remove_vacancy_refuses_while_company_uses_it() {
+
+        let mut schema = schema();
+
+        match schema.remove_vacancy("Janitor", true) {
+            Err(AuthoringError::VacancyInUse { positions, .. }) => assert_eq!(positions, vec!["Фирма/Уборщик"]),
+            other => panic!("Ожидался отказ, получено {:?}", other)
+        }
+        assert!(schema.get_vacancies().contains("Janitor"));
+    }
+
+    #[test]
+    fn remove_vacancy_cascades_to_skills_only_on_request() {
+
+        let mut schema = schema();
+        schema.set_company("Фирма".into(), PositionDocument {
+            position: "Директор".into(),
+            vacancy: "Team_Lead".into(),
+            children: vec![]
+        }).unwrap();
+
+        match schema.remove_vacancy("Janitor", false) {
+            Err(AuthoringError::VacancyInUse { skills, .. }) => assert_eq!(skills, vec!["Ответственность"]),
+            other => panic!("Ожидался отказ, получено {:?}", other)
+        }
+
+        schema.remove_vacancy("Janitor", true).unwrap();
+        assert!(!schema.get_vacancies().contains("Janitor"));
+        assert_eq!(schema.to_document().skills["Ответственность"], BTreeMap::from([("Team_Lead".to_owned(), 3)]));
+    }
+
+    #[test]
+    fn update_vacancy_renames_references() {
+
+        let mut schema = schema();
+        schema.update_vacancy("Team_Lead", "Lead".into(), Some("Руководит командой".into())).unwrap();
+
+        let document = schema.to_document();
+        assert_eq!(document.skills["Ответственность"]["Lead"], 3);
+        assert_eq!(document.companies["Фирма"].vacancy, "Lead");
+    }
+
+    #[test]
+    fn set_skill_rejects_unknown_vacancy() {
+
+        let mut schema = schema();
+        let unknown = schema.set_skill("Пунктуальность".into(), BTreeMap::from([("Programmer".to_owned(), 1)]));
+
+        assert!(matches!(unknown, Err(AuthoringError::Invalid(_))));
+        assert!(!schema.get_skills().contains(&"Пунктуальность".to_owned()));
+    }
+
+    #[test]
+    fn questions_are_added_updated_and_removed() {
+
+        let mut schema = schema();
+
+        let uuid = schema.add_question(question("Вопрос")).unwrap();
+        assert_eq!(uuid, question_uuid("Вопрос"));
+        assert!(matches!(schema.add_question(question("Вопрос")), Err(AuthoringError::AlreadyExists { .. })));
+
+        schema.update_question(&uuid, question("Новый заголовок")).unwrap();
+        assert_eq!(schema.get_questions().get(&uuid).unwrap().get_title(), "Новый заголовок");
+
+        schema.remove_question(&uuid).unwrap();
+        assert!(schema.get_questions().is_empty());
+    }
+
+    #[test]
+    fn saved_scheme_reloads_unchanged() {
+
+        let mut schema = schema();
+        schema.add_question(question("Вопрос")).unwrap();
+
+        let path = std::env::temp_dir().join(format!("authoring_{}.json", std::process::id()));
+        schema.save(&path).unwrap();
+        let reloaded = CoefficientScheme::new(File::open(&path).unwrap()).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(serde_json::to_value(reloaded.to_document()).unwrap(), serde_json::to_value(schema.to_document()).unwrap());
+    }
+
+    #[test]
+    fn edit_ignores_existing_errors() {
+
+        let mut schema = fixtures::scheme(json!({
+            "vacancies": [{ "name": "Team_Lead" }],
+            "skills": { "Ответственность": { "Team_Lead": 99 } }
+        }));
+        assert!(schema.validate().has_errors());
+
+        schema.add_vacancy("Janitor".into(), None).unwrap();
+        assert!(schema.get_vacancies().contains("Janitor"));
+
+        match schema.set_skill("Пунктуальность".into(), BTreeMap::from([("Janitor".to_owned(), 99)])) {
+            Err(AuthoringError::Invalid(report)) => {
+                let pointers = report.errors().map(|d| d.pointer.as_str()).collect::<Vec<&str>>();
+                assert_eq!(pointers, vec!["/skills/Пунктуальность/Janitor"]);
+            }
+            other => panic!("Ожидался отказ, получено {:?}", other)
+        }
+    }
+}
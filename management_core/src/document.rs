@@ -0,0 +1,332 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use serde_path_to_error::Segment;
+
+use crate::{pointer_push, question_uuid, unexpected, variant_uuid, CoefficientScheme, GradingMode, SchemaError};
+
+/// Версия формата skill_coefficients.json, которую понимает парсер схемы
+pub const LATEST_SCHEMA_VERSION: u32 = 2;
+
+/// Актуальная версия документа схемы
+pub type SchemaDocument = SchemaDocumentV2;
+
+// ---------------------------------------------------------------------------
+// Версия 1: исходный формат без поля schema_version
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaDocumentV1 {
+    pub vacancies: Vec<String>,
+    pub skills: BTreeMap<String, BTreeMap<String, i64>>,
+    pub jobs: JobsDocumentV1,
+    pub questions: Vec<QuestionDocumentV1>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobsDocumentV1 {
+    /// Список названий компаний, дублирует ключи `companies`
+    #[serde(default)]
+    pub jobs: Vec<String>,
+    pub companies: BTreeMap<String, JobLevelDocumentV1>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobLevelDocumentV1 {
+    /// Одна пара «должность в компании: вакансия»
+    pub label: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub children: Option<Vec<JobLevelDocumentV1>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuestionDocumentV1 {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uuid: Option<String>,
+    pub title: String,
+    pub variants: Vec<AnswerVariantDocument>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnswerVariantDocument {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub content: String,
+    pub is_answer: bool,
+}
+
+// ---------------------------------------------------------------------------
+// Версия 2: явная версия, описания вакансий, категории и веса вопросов,
+// должности компании без обёртки `jobs` и без map-метки
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaDocumentV2 {
+    pub schema_version: u32,
+    pub vacancies: Vec<VacancyDocument>,
+    pub skills: BTreeMap<String, BTreeMap<String, i64>>,
+    pub companies: BTreeMap<String, PositionDocument>,
+    pub questions: Vec<QuestionDocumentV2>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VacancyDocument {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PositionDocument {
+    /// Название должности в дереве компании
+    pub position: String,
+    /// Вакансия из `vacancies`, которая подходит для должности
+    pub vacancy: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<PositionDocument>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuestionDocumentV2 {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uuid: Option<String>,
+    pub title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weight: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub difficulty: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grading: Option<GradingMode>,
+    pub variants: Vec<AnswerVariantDocument>,
+}
+
+impl SchemaDocumentV1 {
+    fn migrate(self) -> Result<SchemaDocumentV2, SchemaError> {
+
+        let mut companies = BTreeMap::new();
+        for (company_name, tree) in self.jobs.companies {
+            let pointer = pointer_push("/jobs/companies", &company_name);
+            companies.insert(company_name, tree.migrate(&pointer)?);
+        }
+
+        let questions = self.questions
+            .into_iter()
+            .map(|question| {
+                // Записываем в файл те же id, что получили бы вопрос и варианты без них
+                let uuid = question.uuid.unwrap_or_else(|| question_uuid(&question.title));
+
+                let variants = question.variants
+                    .into_iter()
+                    .map(|variant| {
+                        let content = strip_variant_letter(&variant.content).to_owned();
+
+                        AnswerVariantDocument {
+                            id: variant.id.or_else(|| Some(variant_uuid(&uuid, &content))),
+                            content,
+                            is_answer: variant.is_answer
+                        }
+                    })
+                    .collect();
+
+                QuestionDocumentV2 {
+                    uuid: Some(uuid),
+                    title: question.title,
+                    category: None,
+                    weight: None,
+                    difficulty: None,
+                    grading: None,
+                    variants
+                }
+            })
+            .collect();
+
+        Ok(SchemaDocumentV2 {
+            schema_version: 2,
+            vacancies: self.vacancies
+                .into_iter()
+                .map(|name| VacancyDocument { name, description: None })
+                .collect(),
+            skills: self.skills,
+            companies,
+            questions
+        })
+    }
+}
+
+/// В версии 1 буквы вариантов иногда вписаны в текст: «а) …», «б) …».
+/// Начиная с версии 2 буквы расставляются при выдаче теста.
+fn strip_variant_letter(content: &str) -> &str {
+    let mut chars = content.chars();
+
+    match (chars.next(), chars.next()) {
+        (Some(letter), Some(')')) if letter.is_alphabetic() => chars.as_str().trim_start(),
+        _ => content
+    }
+}
+
+impl JobLevelDocumentV1 {
+    fn migrate(self, pointer: &str) -> Result<PositionDocument, SchemaError> {
+
+        if self.label.len() != 1 {
+            return Err(unexpected(
+                &pointer_push(pointer, "label"),
+                "объект из одной пары «должность: вакансия»",
+                &serde_json::to_value(&self.label)?
+            ));
+        }
+        let (position, vacancy) = self.label.into_iter().next().unwrap();
+
+        let mut children = vec![];
+        let children_pointer = pointer_push(pointer, "children");
+        for (ind, child) in self.children.unwrap_or_default().into_iter().enumerate() {
+            children.push(child.migrate(&pointer_push(&children_pointer, &ind.to_string()))?);
+        }
+
+        Ok(PositionDocument { position, vacancy, children })
+    }
+}
+
+/// Документ схемы одной из поддерживаемых версий
+#[derive(Debug, Clone)]
+pub enum VersionedDocument {
+    V1(SchemaDocumentV1),
+    V2(SchemaDocumentV2),
+}
+
+impl VersionedDocument {
+    /// Версия документа: значение `schema_version`, а при его отсутствии 1
+    pub fn detect_version(json: &Value) -> Result<u32, SchemaError> {
+        match json.get("schema_version") {
+            None => Ok(1),
+            Some(version) => version
+                .as_u64()
+                .filter(|version| (1..=LATEST_SCHEMA_VERSION as u64).contains(version))
+                .map(|version| version as u32)
+                .ok_or_else(|| unexpected(
+                    "/schema_version",
+                    "поддерживаемая версия схемы",
+                    version
+                ))
+        }
+    }
+
+    /// Разбирает документ его версии. Актуальная версия проходит через тот же
+    /// разбор, что и [`CoefficientScheme::from_value`].
+    pub fn from_value(json: Value) -> Result<Self, SchemaError> {
+        match VersionedDocument::detect_version(&json)? {
+            1 => Ok(VersionedDocument::V1(deserialize_with_pointer(json)?)),
+            version => Ok(VersionedDocument::V2(CoefficientScheme::parse_latest(&json, version)?.to_document())),
+        }
+    }
+
+    pub fn version(&self) -> u32 {
+        match self {
+            VersionedDocument::V1(_) => 1,
+            VersionedDocument::V2(_) => 2,
+        }
+    }
+
+    /// Применяет миграции по цепочке до актуальной версии
+    pub fn into_latest(self) -> Result<SchemaDocument, SchemaError> {
+        let mut document = self;
+
+        loop {
+            document = match document {
+                VersionedDocument::V1(v1) => VersionedDocument::V2(v1.migrate()?),
+                VersionedDocument::V2(latest) => return Ok(latest),
+            };
+        }
+    }
+}
+
+/// JSON Pointer на место, где serde_path_to_error остановил разбор
+pub(crate) fn path_pointer(path: &serde_path_to_error::Path) -> String {
+    path.iter().fold(String::new(), |pointer, segment| match segment {
+        Segment::Seq { index } => pointer_push(&pointer, &index.to_string()),
+        Segment::Map { key } => pointer_push(&pointer, key),
+        Segment::Enum { variant } => pointer_push(&pointer, variant),
+        Segment::Unknown => pointer_push(&pointer, "?"),
+    })
+}
+
+/// Переводит указатель в документ актуальной версии в указатель на то же
+/// место в документе версии `source_version`. Поля, которых в старой версии
+/// нет, указывают на ближайший объект, из которого они получены миграцией.
+pub(crate) fn source_pointer(source_version: u32, pointer: &str) -> String {
+    if source_version >= LATEST_SCHEMA_VERSION {
+        return pointer.to_owned();
+    }
+
+    let tokens: Vec<&str> = pointer.split('/').skip(1).collect();
+
+    match tokens.as_slice() {
+        ["schema_version", ..] => String::new(),
+        ["vacancies", index, _, ..] => format!("/vacancies/{}", index),
+        ["companies", company, rest @ ..] => {
+            let mut source = format!("/jobs/companies/{}", company);
+            let mut rest = rest;
+
+            while let ["children", index, tail @ ..] = rest {
+                source = format!("{}/children/{}", source, index);
+                rest = tail;
+            }
+
+            // Должность и вакансия в версии 1 — ключ и значение метки
+            if !rest.is_empty() {
+                source.push_str("/label");
+            }
+
+            source
+        }
+        ["questions", index, "category" | "weight" | "difficulty" | "grading", ..] =>
+            format!("/questions/{}", index),
+        _ => pointer.to_owned()
+    }
+}
+
+fn deserialize_with_pointer<T: DeserializeOwned>(json: Value) -> Result<T, SchemaError> {
+    serde_path_to_error::deserialize(json).map_err(|err| SchemaError::Document {
+        pointer: path_pointer(err.path()),
+        description: err.into_inner().to_string()
+    })
+}
+
+/// Переписывает файл схемы в актуальной версии формата.
+/// Возвращает версию, в которой файл был до обновления.
+pub fn upgrade_schema_file(path: &Path) -> Result<u32, SchemaError> {
+
+    let json: Value = serde_json::from_slice(&fs::read(path)?)?;
+    let document = VersionedDocument::from_value(json)?;
+    let source_version = document.version();
+
+    if source_version == LATEST_SCHEMA_VERSION {
+        return Ok(source_version);
+    }
+
+    let latest = document.into_latest()?;
+
+    backup_schema_file(path, source_version)?;
+    write_schema_file(path, &latest)?;
+
+    Ok(source_version)
+}
+
+/// Копия файла схемы в старой версии формата рядом с ним: `*.v1.json.bak`
+pub(crate) fn backup_schema_file(path: &Path, source_version: u32) -> Result<(), SchemaError> {
+    let backup_path = path.with_extension(format!("v{}.json.bak", source_version));
+    fs::copy(path, backup_path)?;
+
+    Ok(())
+}
+
+pub(crate) fn write_schema_file(path: &Path, document: &SchemaDocument) -> Result<(), SchemaError> {
+    // Пишем во временный файл и переименовываем, чтобы не оставить полузаписанную схему
+    let tmp_path = path.with_extension("json.tmp");
+    fs::write(&tmp_path, serde_json::to_vec_pretty(document)?)?;
+    fs::rename(&tmp_path, path)?;
+
+    Ok(())
+}
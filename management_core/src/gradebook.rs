@@ -0,0 +1,371 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::io::{self, Write};
+use rust_xlsxwriter::{Format, Workbook};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::matrix::UTF8_BOM;
+use crate::{SavedSession, SchemaPacks, ToPayload};
+
+/// Столбцы ведомости до столбцов вопросов
+const LEADING_HEADERS: [&str; 6] = ["Сессия", "Студент", "Группа", "Начало (UTC)", "Завершение (UTC)", "Балл теста"];
+
+/// Столбцы ведомости после столбцов вопросов
+const TRAILING_HEADERS: [&str; 2] = ["Балл расстановки", "Компания"];
+
+/// Начала ячейки, с которыми Excel выполняет её как формулу
+pub(crate) const FORMULA_PREFIXES: [char; 6] = ['=', '+', '-', '@', '\t', '\r'];
+
+/// Текст для ячейки CSV. Excel выполняет ячейку, которая начинается с `=`,
+/// `+`, `-` или `@`, как формулу, а имена и группы вводят сами студенты.
+pub(crate) fn csv_text(text: &str) -> String {
+    if text.starts_with(FORMULA_PREFIXES) {
+        return format!("'{}", text);
+    }
+
+    text.to_owned()
+}
+
+#[derive(Debug)]
+pub enum GradebookError {
+    Io(io::Error),
+    Csv(csv::Error),
+    Xlsx(rust_xlsxwriter::XlsxError)
+}
+
+impl From<io::Error> for GradebookError {
+    fn from(value: io::Error) -> Self {
+        GradebookError::Io(value)
+    }
+}
+
+impl From<csv::Error> for GradebookError {
+    fn from(value: csv::Error) -> Self {
+        GradebookError::Csv(value)
+    }
+}
+
+impl From<rust_xlsxwriter::XlsxError> for GradebookError {
+    fn from(value: rust_xlsxwriter::XlsxError) -> Self {
+        GradebookError::Xlsx(value)
+    }
+}
+
+impl Display for GradebookError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GradebookError::Io(err) => write!(f, "Не удалось записать ведомость: {}", err),
+            GradebookError::Csv(err) => write!(f, "Не удалось записать ведомость CSV: {}", err),
+            GradebookError::Xlsx(err) => write!(f, "Не удалось записать ведомость XLSX: {}", err)
+        }
+    }
+}
+
+impl std::error::Error for GradebookError {}
+
+impl ToPayload for GradebookError {
+    fn code_and_details(&self) -> (&'static str, Value) {
+        match self {
+            GradebookError::Io(err) => ("gradebook_io", json!({ "kind": format!("{:?}", err.kind()) })),
+            GradebookError::Csv(err) => (
+                "gradebook_csv",
+                json!({ "line": err.position().map(|position| position.line()) })
+            ),
+            GradebookError::Xlsx(_) => ("gradebook_xlsx", Value::Null)
+        }
+    }
+}
+
+/// Столбец вопроса в ведомости
+#[derive(Debug, Clone, Serialize)]
+pub struct GradebookQuestion {
+    pub uuid: String,
+    /// Заголовок вопроса, или uuid, если вопроса нет ни в одном пакете
+    pub title: String
+}
+
+/// Строка ведомости: одна сессия студента
+#[derive(Debug, Clone, Serialize)]
+pub struct GradebookRow {
+    pub session_id: i64,
+    pub student_name: String,
+    pub student_group: Option<String>,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    /// Взвешенная доля балла теста от 0 до 1, `None` — тест не проходился
+    pub quiz_score: Option<f64>,
+    /// Доля балла за каждый вопрос из [`Gradebook::questions`], `None` — вопрос не выдавался
+    pub credits: Vec<Option<f64>>,
+    pub placement_score: Option<f64>,
+    pub placement_company: Option<String>
+}
+
+/// Ведомость группы по сохранённым сессиям
+#[derive(Debug, Clone, Serialize)]
+pub struct Gradebook {
+    /// Вопросы, на которые отвечали в сессиях ведомости, по заголовку
+    pub questions: Vec<GradebookQuestion>,
+    /// По группе, имени студента и времени начала
+    pub rows: Vec<GradebookRow>
+}
+
+impl Gradebook {
+    /// Заголовки вопросов ищутся по uuid во всех пакетах, по первому
+    /// пакету, где вопрос есть
+    pub fn new(sessions: &[SavedSession], packs: &SchemaPacks) -> Self {
+
+        let mut titles = BTreeMap::new();
+        for session in sessions {
+            for answer in &session.answers {
+                titles.entry(answer.question_uuid.clone()).or_insert_with(|| {
+                    packs
+                        .iter()
+                        .flat_map(|pack| pack.get_schema().get_questions().iter())
+                        .find(|question| question.get_uuid() == &answer.question_uuid)
+                        .map_or_else(|| answer.question_uuid.clone(), |question| question.get_title().clone())
+                });
+            }
+        }
+
+        let mut questions = titles
+            .into_iter()
+            .map(|(uuid, title)| GradebookQuestion { uuid, title })
+            .collect::<Vec<GradebookQuestion>>();
+        questions.sort_by(|a, b| a.title.cmp(&b.title).then_with(|| a.uuid.cmp(&b.uuid)));
+
+        let mut rows = sessions
+            .iter()
+            .map(|session| {
+                let (points, weight) = session.answers
+                    .iter()
+                    .fold((0.0, 0.0), |(points, weight), answer| (points + answer.credit * answer.weight, weight + answer.weight));
+
+                GradebookRow {
+                    session_id: session.session_id,
+                    student_name: session.student_name.clone(),
+                    student_group: session.student_group.clone(),
+                    started_at: session.created_at.clone(),
+                    finished_at: session.finished_at.clone(),
+                    quiz_score: if session.answers.is_empty() {
+                        None
+                    } else if weight == 0.0 {
+                        Some(0.0)
+                    } else {
+                        Some(points / weight)
+                    },
+                    credits: questions
+                        .iter()
+                        .map(|question| session.answers
+                            .iter()
+                            .find(|answer| answer.question_uuid == question.uuid)
+                            .map(|answer| answer.credit))
+                        .collect(),
+                    placement_score: session.placement.as_ref().map(|placement| placement.score),
+                    placement_company: session.placement.as_ref().and_then(|placement| placement.company.clone())
+                }
+            })
+            .collect::<Vec<GradebookRow>>();
+
+        rows.sort_by(|a, b| a.student_group
+            .cmp(&b.student_group)
+            .then_with(|| a.student_name.cmp(&b.student_name))
+            .then_with(|| a.started_at.cmp(&b.started_at))
+            .then_with(|| a.session_id.cmp(&b.session_id)));
+
+        Self { questions, rows }
+    }
+
+    fn headers(&self) -> Vec<&str> {
+        LEADING_HEADERS
+            .iter()
+            .copied()
+            .chain(self.questions.iter().map(|question| question.title.as_str()))
+            .chain(TRAILING_HEADERS.iter().copied())
+            .collect()
+    }
+
+    /// CSV с BOM, чтобы Excel открыл кириллицу. Доли балла — числа от 0 до 1.
+    pub fn write_csv<W: Write>(&self, mut writer: W) -> Result<(), GradebookError> {
+        writer.write_all(UTF8_BOM.as_bytes())?;
+
+        let mut csv = csv::Writer::from_writer(writer);
+        csv.write_record(self.headers().into_iter().map(csv_text))?;
+
+        let number = |value: Option<f64>| value.map_or(String::new(), |value| format!("{:.3}", value));
+
+        for row in &self.rows {
+            let mut record = vec![
+                row.session_id.to_string(),
+                csv_text(&row.student_name),
+                row.student_group.as_deref().map(csv_text).unwrap_or_default(),
+                row.started_at.clone(),
+                row.finished_at.clone().unwrap_or_default(),
+                number(row.quiz_score)
+            ];
+            record.extend(row.credits.iter().map(|credit| number(*credit)));
+            record.push(number(row.placement_score));
+            record.push(row.placement_company.as_deref().map(csv_text).unwrap_or_default());
+
+            csv.write_record(record)?;
+        }
+
+        csv.flush()?;
+
+        Ok(())
+    }
+
+    /// Книга XLSX с ведомостью на первом листе. Балл теста и баллы
+    /// за вопросы — числа с процентным форматом, тексты пишутся только
+    /// строками и формулами не становятся.
+    pub fn to_xlsx(&self) -> Result<Vec<u8>, GradebookError> {
+
+        let mut workbook = Workbook::new();
+        let bold = Format::new().set_bold();
+        let percent = Format::new().set_num_format("0%");
+        let sheet = workbook.add_worksheet();
+        sheet.set_name("Ведомость")?;
+
+        for (column, header) in self.headers().into_iter().enumerate() {
+            sheet.write_string_with_format(0, column as u16, header, &bold)?;
+        }
+
+        let questions_column = LEADING_HEADERS.len() as u16;
+        let placement_column = questions_column + self.questions.len() as u16;
+
+        for (index, row) in self.rows.iter().enumerate() {
+            let line = index as u32 + 1;
+
+            sheet.write_number(line, 0, row.session_id as f64)?;
+            sheet.write_string(line, 1, &row.student_name)?;
+            if let Some(group) = &row.student_group {
+                sheet.write_string(line, 2, group)?;
+            }
+            sheet.write_string(line, 3, &row.started_at)?;
+            if let Some(finished_at) = &row.finished_at {
+                sheet.write_string(line, 4, finished_at)?;
+            }
+            if let Some(score) = row.quiz_score {
+                sheet.write_number_with_format(line, 5, score, &percent)?;
+            }
+
+            for (offset, credit) in row.credits.iter().enumerate() {
+                if let Some(credit) = credit {
+                    sheet.write_number_with_format(line, questions_column + offset as u16, *credit, &percent)?;
+                }
+            }
+
+            if let Some(score) = row.placement_score {
+                sheet.write_number(line, placement_column, score)?;
+            }
+            if let Some(company) = &row.placement_company {
+                sheet.write_string(line, placement_column + 1, company)?;
+            }
+        }
+
+        sheet.set_column_width(1, 30)?;
+        sheet.set_column_width(3, 20)?;
+        sheet.set_column_width(4, 20)?;
+        sheet.set_freeze_panes(1, 2)?;
+
+        Ok(workbook.save_to_buffer()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{fixtures, question_uuid, Page, PlacementScoreRecord, QuizAnswerRecord, ResultFilter, SchemaPack, Storage};
+    use super::*;
+
+    /// Журнал из двух сессий: начатой без ответов и завершённой с ответом
+    /// на вопрос, которого уже нет в схеме
+    fn gradebook() -> Gradebook {
+        let schema = fixtures::scheme(json!({
+            "vacancies": [{ "name": "Team_Lead" }],
+            "skills": { "Ответственность": { "Team_Lead": 3 } },
+            "questions": [{
+                "title": "Лидер — это:",
+                "variants": [{ "content": "да", "is_answer": true }, { "content": "нет", "is_answer": false }]
+            }]
+        }));
+        let mut packs = SchemaPacks::default();
+        packs.insert(SchemaPack::new("it".into(), "it.json".into(), schema));
+
+        let mut storage = Storage::open_in_memory().unwrap();
+        let petrov = storage.create_student("Петров", Some("ПИ-21")).unwrap();
+        let ivanov = storage.create_student("=HYPERLINK(\"http://evil\")", Some("ПИ-21")).unwrap();
+
+        let session = storage.start_session(petrov.id).unwrap();
+        storage.record_quiz_answers(session.id, &[
+            QuizAnswerRecord::from_result(question_uuid("Лидер — это:"), true),
+            QuizAnswerRecord { weight: 3.0, ..QuizAnswerRecord::from_result("удалённый".into(), false) }
+        ]).unwrap();
+        storage.record_placement(session.id, &PlacementScoreRecord { company: Some("Консалтинг".into()), score: 0.5 }).unwrap();
+        storage.finish_session(session.id).unwrap();
+        storage.start_session(ivanov.id).unwrap();
+
+        let sessions = storage.query_sessions(&ResultFilter::default(), &Page::default()).unwrap().items;
+        Gradebook::new(&sessions, &packs)
+    }
+
+    fn csv_lines(gradebook: &Gradebook) -> Vec<String> {
+        let mut csv = vec![];
+        gradebook.write_csv(&mut csv).unwrap();
+        String::from_utf8(csv).unwrap().lines().map(str::to_owned).collect()
+    }
+
+    #[test]
+    fn gradebook_keeps_removed_questions() {
+
+        let gradebook = gradebook();
+
+        assert_eq!(
+            gradebook.questions.iter().map(|question| question.title.as_str()).collect::<Vec<&str>>(),
+            vec!["Лидер — это:", "удалённый"]
+        );
+    }
+
+    #[test]
+    fn unanswered_session_has_empty_credits() {
+
+        let row = &gradebook().rows[0];
+
+        assert_eq!(row.student_name, "=HYPERLINK(\"http://evil\")");
+        assert_eq!(row.quiz_score, None);
+        assert_eq!(row.credits, vec![None, None]);
+    }
+
+    #[test]
+    fn finished_session_has_weighted_scores() {
+
+        let row = &gradebook().rows[1];
+
+        assert_eq!(row.quiz_score, Some(0.25));
+        assert_eq!(row.credits, vec![Some(1.0), Some(0.0)]);
+        assert_eq!(row.placement_score, Some(0.5));
+    }
+
+    #[test]
+    fn gradebook_csv_has_row_per_session() {
+
+        let lines = csv_lines(&gradebook());
+
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].ends_with("Балл теста,Лидер — это:,удалённый,Балл расстановки,Компания"));
+        assert!(lines[2].ends_with(",0.250,1.000,0.000,0.500,Консалтинг"));
+    }
+
+    #[test]
+    fn gradebook_csv_escapes_formulas() {
+
+        let lines = csv_lines(&gradebook());
+
+        assert!(lines[1].contains(",\"'=HYPERLINK(\"\"http://evil\"\")\",ПИ-21,"));
+    }
+
+    #[test]
+    fn gradebook_exports_xlsx() {
+
+        assert!(!gradebook().to_xlsx().unwrap().is_empty());
+    }
+}
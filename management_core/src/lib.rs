@@ -1,32 +1,69 @@
-#![feature(ptr_internals)]
-
+mod adaptive;
+mod analysis;
+mod archive;
+mod authoring;
+mod document;
+mod gradebook;
+mod matrix;
 mod models;
-
+mod packs;
+mod placement;
+mod quiz;
+mod scoring;
+mod solver;
+mod storage;
+mod validation;
+
+pub use adaptive::*;
+pub use analysis::*;
+pub use archive::*;
+pub use authoring::*;
+pub use document::*;
+pub use gradebook::*;
+pub use matrix::*;
 pub use models::*;
-
-use std::{fs, io::{self, Read}, collections::{HashSet, HashMap}, hash::{Hash, Hasher}, rc::{Rc, Weak as RcWeak}, mem};
-use std::arch::x86_64::CpuidResult;
-use std::borrow::Borrow;
-use std::cell::RefCell;
-use std::fmt::{Debug, Formatter};
-use std::fs::File;
+pub use packs::*;
+pub use placement::*;
+pub use quiz::*;
+pub use scoring::*;
+pub use solver::*;
+pub use storage::*;
+pub use validation::*;
+
+use std::{fs, io::{self, Read}, collections::{HashSet, HashMap}};
+use std::fmt::{Debug, Display, Formatter};
 use std::io::Error;
-use std::iter::Map;
-use std::path::Path;
-use std::ptr::Unique;
-use std::slice::Iter;
-use std::sync::{Arc, OnceLock, Weak as ArcWeak};
-use std::vec::IntoIter;
-use serde::{Deserialize, Deserializer, Serialize, Serializer};
-use serde::ser::SerializeStruct;
-use uuid::Uuid;
+use serde::Serialize;
 
-use serde_json::{Value, from_reader};
+use serde_json::{json, Map, Value};
 
 
 #[derive(Debug)]
 pub enum SchemaError {
     IoError(io::Error),
+    /// Файл схемы не является корректным JSON
+    Syntax {
+        line: usize,
+        column: usize,
+        description: String
+    },
+    /// Обязательный ключ отсутствует в схеме
+    MissingField {
+        pointer: String
+    },
+    /// Значение по указателю не совпадает с ожидаемым
+    Unexpected {
+        pointer: String,
+        expected: &'static str,
+        found: String
+    },
+    /// Схема разобрана, но валидатор нашёл ошибки
+    Invalid(ValidationReport),
+    /// Документ не соответствует типизированной модели своей версии
+    Document {
+        pointer: String,
+        description: String
+    },
     Custom {
         name: String,
         description: String
@@ -39,13 +76,210 @@ impl From<io::Error> for SchemaError {
     }
 }
 
+impl From<serde_json::Error> for SchemaError {
+    fn from(value: serde_json::Error) -> Self {
+        Self::Syntax {
+            line: value.line(),
+            column: value.column(),
+            description: value.to_string()
+        }
+    }
+}
+
+impl Display for SchemaError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SchemaError::IoError(err) =>
+                write!(f, "Ошибка чтения файла схемы: {}", err),
+            SchemaError::Syntax { line, column, description } =>
+                write!(f, "Некорректный JSON (строка {}, столбец {}): {}", line, column, description),
+            SchemaError::MissingField { pointer } =>
+                write!(f, "{}: обязательное поле отсутствует", pointer),
+            SchemaError::Unexpected { pointer, expected, found } =>
+                write!(f, "{}: ожидалось {}, найдено {}", pointer, expected, found),
+            SchemaError::Document { pointer, description } =>
+                write!(f, "{}: {}", pointer, description),
+            SchemaError::Invalid(report) =>
+                write!(f, "Схема содержит ошибки. {}", report),
+            SchemaError::Custom { name, description } =>
+                write!(f, "{}: {}", name, description),
+        }
+    }
+}
+
+impl std::error::Error for SchemaError {}
+
+/// Единый вид ошибки для фронтенда: `{code, message, details}`
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorPayload {
+    pub code: String,
+    pub message: String,
+    pub details: Value
+}
+
+/// Ошибка, которая уходит во фронтенд как [`ErrorPayload`]. Сообщение —
+/// текст `Display` ошибки.
+pub trait ToPayload: Display {
+    fn code_and_details(&self) -> (&'static str, Value);
+
+    fn to_payload(&self) -> ErrorPayload {
+        let (code, details) = self.code_and_details();
+
+        ErrorPayload {
+            code: code.to_owned(),
+            message: self.to_string(),
+            details
+        }
+    }
+}
+
+/// `Serialize` для ошибок с [`ToPayload`]: сериализуются как [`ErrorPayload`]
+#[macro_export]
+macro_rules! serialize_as_payload {
+    ($($error:ty),+ $(,)?) => {
+        $(
+            impl ::serde::Serialize for $error {
+                fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: ::serde::Serializer {
+                    ::serde::Serialize::serialize(&$crate::ToPayload::to_payload(self), serializer)
+                }
+            }
+        )+
+    };
+}
+
+impl SchemaError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            SchemaError::IoError(_) => "schema_io",
+            SchemaError::Syntax { .. } => "schema_syntax",
+            SchemaError::MissingField { .. } => "schema_missing_field",
+            SchemaError::Unexpected { .. } => "schema_unexpected",
+            SchemaError::Invalid(_) => "schema_invalid",
+            SchemaError::Document { .. } => "schema_document",
+            SchemaError::Custom { .. } => "schema_custom",
+        }
+    }
+
+    pub fn details(&self) -> Value {
+        match self {
+            SchemaError::IoError(err) => json!({ "kind": format!("{:?}", err.kind()) }),
+            SchemaError::Syntax { line, column, .. } => json!({ "line": line, "column": column }),
+            SchemaError::MissingField { pointer } => json!({ "pointer": pointer }),
+            SchemaError::Unexpected { pointer, expected, found } =>
+                json!({ "pointer": pointer, "expected": expected, "found": found }),
+            SchemaError::Invalid(report) => json!(report),
+            SchemaError::Document { pointer, .. } => json!({ "pointer": pointer }),
+            SchemaError::Custom { name, .. } => json!({ "name": name }),
+        }
+    }
+
+    /// Переводит указатели ошибки разбора мигрированного документа в
+    /// указатели на исходный документ версии `source_version`
+    pub(crate) fn with_source_pointer(self, source_version: u32) -> Self {
+        match self {
+            SchemaError::MissingField { pointer } =>
+                SchemaError::MissingField { pointer: source_pointer(source_version, &pointer) },
+            SchemaError::Unexpected { pointer, expected, found } =>
+                SchemaError::Unexpected { pointer: source_pointer(source_version, &pointer), expected, found },
+            SchemaError::Document { pointer, description } =>
+                SchemaError::Document { pointer: source_pointer(source_version, &pointer), description },
+            SchemaError::Invalid(mut report) => {
+                for diagnostic in &mut report.diagnostics {
+                    diagnostic.pointer = source_pointer(source_version, &diagnostic.pointer);
+                }
+                SchemaError::Invalid(report)
+            }
+            err => err
+        }
+    }
+
+}
+
+impl ToPayload for SchemaError {
+    fn code_and_details(&self) -> (&'static str, Value) {
+        (self.code(), self.details())
+    }
+}
+
+serialize_as_payload!(
+    SchemaError, ScoringError, PlacementError, StorageError, QuizError, AuthoringError, ArchiveError, MatrixError,
+    GradebookError
+);
+
+/// Добавляет ключ к JSON pointer (RFC 6901)
+pub(crate) fn pointer_push(pointer: &str, token: &str) -> String {
+    format!("{}/{}", pointer, token.replace('~', "~0").replace('/', "~1"))
+}
+
+fn describe_value(value: &Value) -> String {
+    let type_name = match value {
+        Value::Null => "null",
+        Value::Bool(_) => "логическое значение",
+        Value::Number(_) => "число",
+        Value::String(_) => "строка",
+        Value::Array(_) => "массив",
+        Value::Object(_) => "объект",
+    };
+
+    let mut rendered = value.to_string();
+    if rendered.chars().count() > 40 {
+        rendered = rendered.chars().take(40).collect::<String>() + "…";
+    }
+
+    match value {
+        Value::Null => type_name.to_owned(),
+        Value::Array(arr) => format!("{} из {} элементов", type_name, arr.len()),
+        Value::Object(obj) => format!("{} из {} полей", type_name, obj.len()),
+        _ => format!("{} {}", type_name, rendered)
+    }
+}
+
+pub(crate) fn unexpected(pointer: &str, expected: &'static str, found: &Value) -> SchemaError {
+    SchemaError::Unexpected {
+        pointer: pointer.to_owned(),
+        expected,
+        found: describe_value(found)
+    }
+}
+
+fn expect_field<'a>(object: &'a Map<String, Value>, key: &str, pointer: &str) -> Result<&'a Value, SchemaError> {
+    object
+        .get(key)
+        .ok_or(SchemaError::MissingField { pointer: pointer_push(pointer, key) })
+}
+
+fn expect_object<'a>(value: &'a Value, pointer: &str) -> Result<&'a Map<String, Value>, SchemaError> {
+    value.as_object().ok_or_else(|| unexpected(pointer, "объект", value))
+}
+
+fn expect_array<'a>(value: &'a Value, pointer: &str) -> Result<&'a Vec<Value>, SchemaError> {
+    value.as_array().ok_or_else(|| unexpected(pointer, "массив", value))
+}
+
+fn expect_str<'a>(value: &'a Value, pointer: &str) -> Result<&'a str, SchemaError> {
+    value.as_str().ok_or_else(|| unexpected(pointer, "строка", value))
+}
+
+fn expect_i64(value: &Value, pointer: &str) -> Result<i64, SchemaError> {
+    value.as_i64().ok_or_else(|| unexpected(pointer, "целое число", value))
+}
+
+fn expect_bool(value: &Value, pointer: &str) -> Result<bool, SchemaError> {
+    value.as_bool().ok_or_else(|| unexpected(pointer, "логическое значение", value))
+}
+
 // Связать vacancies и skills обычной ссылкой с верменем жизни, а не RC
 #[derive(Debug, Clone, Serialize)]
 pub struct CoefficientScheme {
-    vacancies: HashSet<Vacancy>,
+    vacancies: VacancyRegistry,
     skills: HashSet<Skill>,
     companies: HashSet<Company>,
     questions: HashSet<Question>,
+    /// Проблемы, найденные при разборе файла
+    #[serde(skip)]
+    diagnostics: ValidationReport,
+    /// Версия формата файла до миграции
+    source_version: u32,
 }
 
 impl CoefficientScheme {
@@ -54,79 +288,121 @@ impl CoefficientScheme {
         let mut schema_bytes = vec![];
         schema_f.read_to_end(&mut schema_bytes)?;
 
-        let json: Value = serde_json::from_slice(&schema_bytes).unwrap();
-        println!("------------\nJson schema: {:#?}\n------------", json);
+        let json: Value = serde_json::from_slice(&schema_bytes)?;
+
+        CoefficientScheme::from_value(&json)
+    }
+
+    /// Разбирает схему любой поддерживаемой версии. Старые версии сначала
+    /// мигрируют до [`LATEST_SCHEMA_VERSION`] и разбираются тем же парсером,
+    /// указатели в ошибках при этом относятся к исходному документу.
+    pub fn from_value(json: &Value) -> Result<Self, SchemaError> {
+
+        let source_version = VersionedDocument::detect_version(json)?;
+
+        if source_version == LATEST_SCHEMA_VERSION {
+            return CoefficientScheme::parse_latest(json, source_version);
+        }
+
+        let latest = VersionedDocument::from_value(json.clone())?.into_latest()?;
+
+        CoefficientScheme::parse_latest(&serde_json::to_value(latest)?, source_version)
+            .map_err(|err| err.with_source_pointer(source_version))
+    }
+
+    fn parse_latest(json: &Value, source_version: u32) -> Result<Self, SchemaError> {
+
+        let root = expect_object(json, "")?;
+        let mut diagnostics = ValidationReport::default();
 
         let vacancies =
-            CoefficientScheme::parse_vacancies(&json["vacancies"]);
-        println!("Успешных парсинг вакансий\n------------");
+            CoefficientScheme::parse_vacancies(expect_field(root, "vacancies", "")?, &mut diagnostics)?;
 
-        let skills =
-            CoefficientScheme::parse_skills(&json["skills"], &vacancies);
-        println!("Успешных парсинг навыков\n------------");
+        let skills = CoefficientScheme::parse_skills(
+            expect_field(root, "skills", "")?,
+            &vacancies,
+            &mut diagnostics
+        )?;
 
         let companies =
-            CoefficientScheme::parse_companies(&json["jobs"]["companies"]);
-        println!("Успешных парсинг компаний\n------------");
+            CoefficientScheme::parse_companies(expect_field(root, "companies", "")?)?;
 
         let questions =
-            CoefficientScheme::parse_questions(&json["questions"]);
-        println!("Успешных парсинг вопросов------------");
+            CoefficientScheme::parse_questions(expect_field(root, "questions", "")?, &mut diagnostics)?;
 
-        return Ok(Self {
+        Ok(Self {
             vacancies,
             skills,
             companies,
-            questions
-        });
+            questions,
+            diagnostics,
+            source_version
+        })
     }
 
-    fn parse_vacancies(value: &Value) -> HashSet<Vacancy> {
-        let iter = value
-            .as_array()
-            .expect("json конфиг не содержит массива в поле 'vacancies'")
-            .iter()
-            .map(
-                |vacancy| {
-                    let vacancy_name_str = vacancy
-                        .as_str()
-                        .unwrap();
-                    return Vacancy(vacancy_name_str.into());
-                }
-            );
+    fn parse_vacancies(value: &Value, diagnostics: &mut ValidationReport) -> Result<VacancyRegistry, SchemaError> {
+        let mut vacancies = VacancyRegistry::default();
+
+        for (ind, vacancy) in expect_array(value, "/vacancies")?.iter().enumerate() {
+            let vacancy_pointer = pointer_push("/vacancies", &ind.to_string());
+            let vacancy_obj = expect_object(vacancy, &vacancy_pointer)?;
+            let vacancy_name_str = expect_str(
+                expect_field(vacancy_obj, "name", &vacancy_pointer)?,
+                &pointer_push(&vacancy_pointer, "name")
+            )?;
+
+            if vacancies.contains(vacancy_name_str) {
+                diagnostics.warning(
+                    "duplicate_vacancy",
+                    vacancy_pointer.clone(),
+                    format!("Вакансия {:?} объявлена повторно", vacancy_name_str)
+                );
+            }
+            let vacancy_id = vacancies.intern(Vacancy(vacancy_name_str.into()));
+
+            if let Some(description) = vacancy_obj.get("description").filter(|d| !d.is_null()) {
+                let description = expect_str(description, &pointer_push(&vacancy_pointer, "description"))?;
+                vacancies.set_description(vacancy_id, description.to_owned());
+            }
+        }
 
-        return HashSet::from_iter(iter);
+        Ok(vacancies)
     }
 
-    fn parse_skills(value: &Value, vacancies: &HashSet<Vacancy>) -> HashSet<Skill> {
+    fn parse_skills(
+        value: &Value,
+        vacancies: &VacancyRegistry,
+        diagnostics: &mut ValidationReport
+    ) -> Result<HashSet<Skill>, SchemaError> {
 
-        let skills = value
-            .as_object()
-            .expect("json конфиг не содержит объекта в поле 'skills'");
+        let skills = expect_object(value, "/skills")?;
 
         let mut res_skills = HashSet::default();
 
         for (skill_name, vacancies_coef) in skills {
 
-            let vacancies_coef_map = vacancies_coef.as_object().unwrap();
+            let skill_pointer = pointer_push("/skills", skill_name);
+            let vacancies_coef_map = expect_object(vacancies_coef, &skill_pointer)?;
             let mut vacancies_coefficient = Vec::default();
 
             for (vacancy_name, vacancy_coef) in vacancies_coef_map {
 
-                let vacancy: Vacancy = vacancy_name.clone().into();
-                let vacancy_rc = vacancies
-                    .get::<Vacancy>(&vacancy)
-                    .ok_or(
-                        SchemaError::Custom {
-                            name: "Not found vacancy in HasSet".to_owned(),
-                            description: format!("В хешсете с вакансиями не найдена вакансия: {:?}", vacancy_name)
-                        }
-                    ).unwrap();
-
-                let vacancy_coefficient = VacancyCoefficient::new(
-                    &*vacancy_rc,
-                    vacancy_coef.as_i64().unwrap()
-                );
+                let coef_pointer = pointer_push(&skill_pointer, vacancy_name);
+                let coefficient = expect_i64(vacancy_coef, &coef_pointer)?;
+
+                let vacancy_id = match vacancies.get_id(vacancy_name) {
+                    Some(vacancy_id) => vacancy_id,
+                    None => {
+                        diagnostics.error(
+                            "unknown_vacancy",
+                            coef_pointer,
+                            format!("Вакансия {:?} не объявлена в /vacancies", vacancy_name)
+                        );
+                        continue
+                    }
+                };
+
+                let vacancy_coefficient = VacancyCoefficient::new(vacancy_id, coefficient);
 
                 vacancies_coefficient.push(vacancy_coefficient);
             }
@@ -137,7 +413,7 @@ impl CoefficientScheme {
             });
         }
 
-        return res_skills;
+        Ok(res_skills)
     }
 
     // fn parse_jobs(value: &Value) -> HashSet<Job> {
@@ -159,44 +435,369 @@ impl CoefficientScheme {
     //         .collect::<HashSet<Job>>();
     // }
 
-    fn parse_companies(value: &Value) -> HashSet<Company> {
-
-        let companies_map = value
-            .as_object()
-            .expect("json конфиг не содержит объекта в поле 'companies'");
-
-        return companies_map
-            .into_iter()
-            .map(|company| {
-                //println!("{}", job.1);
-                let company_graph =
-                    serde_json::from_value::<JobLevel>(company.1.clone())
-                        .unwrap();
-                Company {
-                    name: company.0.into(),
-                    tree: company_graph
+    fn parse_companies(value: &Value) -> Result<HashSet<Company>, SchemaError> {
+
+        let companies_map = expect_object(value, "/companies")?;
+
+        let mut companies = HashSet::default();
+
+        for (company_name, company_tree) in companies_map {
+            let company_graph = CoefficientScheme::parse_job_level(
+                company_tree,
+                &pointer_push("/companies", company_name)
+            )?;
+
+            companies.insert(Company {
+                name: company_name.clone(),
+                tree: company_graph
+            });
+        }
+
+        Ok(companies)
+    }
+
+    fn parse_job_level(value: &Value, pointer: &str) -> Result<JobLevel, SchemaError> {
+
+        let level = expect_object(value, pointer)?;
+
+        let position = expect_str(
+            expect_field(level, "position", pointer)?,
+            &pointer_push(pointer, "position")
+        )?;
+        let vacancy = expect_str(
+            expect_field(level, "vacancy", pointer)?,
+            &pointer_push(pointer, "vacancy")
+        )?;
+
+        let mut label = HashMap::default();
+        label.insert(position.to_owned(), vacancy.to_owned());
+
+        let children = match level.get("children") {
+            None | Some(Value::Null) => None,
+            Some(children_value) => {
+                let children_pointer = pointer_push(pointer, "children");
+                let mut children = vec![];
+
+                for (ind, child) in expect_array(children_value, &children_pointer)?.iter().enumerate() {
+                    children.push(CoefficientScheme::parse_job_level(
+                        child,
+                        &pointer_push(&children_pointer, &ind.to_string())
+                    )?);
                 }
-            })
-            .collect::<HashSet<Company>>();
+
+                Some(children)
+            }
+        };
+
+        Ok(JobLevel { label, children })
+    }
+
+    fn parse_questions(value: &Value, diagnostics: &mut ValidationReport) -> Result<HashSet<Question>, SchemaError> {
+
+        let mut questions = HashSet::default();
+
+        for (ind, question_value) in expect_array(value, "/questions")?.iter().enumerate() {
+
+            let question_pointer = pointer_push("/questions", &ind.to_string());
+            let question = expect_object(question_value, &question_pointer)?;
+
+            let title = expect_str(
+                expect_field(question, "title", &question_pointer)?,
+                &pointer_push(&question_pointer, "title")
+            )?.to_owned();
+
+            let uuid = match question.get("uuid") {
+                None | Some(Value::Null) => question_uuid(&title),
+                Some(uuid) => expect_str(uuid, &pointer_push(&question_pointer, "uuid"))?.to_owned()
+            };
+
+            let category = match question.get("category") {
+                None | Some(Value::Null) => None,
+                Some(category) =>
+                    Some(expect_str(category, &pointer_push(&question_pointer, "category"))?.to_owned())
+            };
+
+            let weight = match question.get("weight") {
+                None | Some(Value::Null) => None,
+                Some(weight) => Some(
+                    weight
+                        .as_f64()
+                        .ok_or_else(|| unexpected(&pointer_push(&question_pointer, "weight"), "число", weight))?
+                )
+            };
+
+            if weight.is_some_and(|weight| !weight.is_finite() || weight <= 0.0) {
+                diagnostics.error(
+                    "invalid_weight",
+                    pointer_push(&question_pointer, "weight"),
+                    format!("Вес вопроса {:?} должен быть положительным числом", title)
+                );
+            }
+
+            let difficulty = match question.get("difficulty") {
+                None | Some(Value::Null) => None,
+                Some(difficulty) => {
+                    let difficulty_pointer = pointer_push(&question_pointer, "difficulty");
+                    let level = expect_i64(difficulty, &difficulty_pointer)?;
+
+                    if DIFFICULTY_RANGE.contains(&level) {
+                        Some(level as u8)
+                    } else {
+                        diagnostics.error(
+                            "difficulty_out_of_range",
+                            difficulty_pointer,
+                            format!(
+                                "Сложность {} вне диапазона {}..={}",
+                                level,
+                                DIFFICULTY_RANGE.start(),
+                                DIFFICULTY_RANGE.end()
+                            )
+                        );
+                        None
+                    }
+                }
+            };
+
+            let grading = match question.get("grading") {
+                None | Some(Value::Null) => None,
+                Some(grading) => Some(
+                    serde_json::from_value::<GradingMode>(grading.clone()).map_err(|_| unexpected(
+                        &pointer_push(&question_pointer, "grading"),
+                        "single_choice, exact_match или partial_credit",
+                        grading
+                    ))?
+                )
+            };
+
+            let variants_pointer = pointer_push(&question_pointer, "variants");
+            let mut variants: Vec<AnswerVariant> = vec![];
+
+            for (var_ind, variant_value) in expect_array(
+                expect_field(question, "variants", &question_pointer)?,
+                &variants_pointer
+            )?.iter().enumerate() {
+
+                let variant_pointer = pointer_push(&variants_pointer, &var_ind.to_string());
+                let variant = expect_object(variant_value, &variant_pointer)?;
+
+                let content = expect_str(
+                    expect_field(variant, "content", &variant_pointer)?,
+                    &pointer_push(&variant_pointer, "content")
+                )?.to_owned();
+
+                let is_answer = expect_bool(
+                    expect_field(variant, "is_answer", &variant_pointer)?,
+                    &pointer_push(&variant_pointer, "is_answer")
+                )?;
+
+                let id = match variant.get("id") {
+                    None | Some(Value::Null) => variant_uuid(&uuid, &content),
+                    Some(id) => expect_str(id, &pointer_push(&variant_pointer, "id"))?.to_owned()
+                };
+
+                if variants.iter().any(|other| other.content == content || other.id == id) {
+                    diagnostics.error(
+                        "duplicate_variant",
+                        variant_pointer,
+                        format!("Вариант {:?} повторяется в вопросе", content)
+                    );
+                    continue
+                }
+
+                variants.push(AnswerVariant { id, content, is_answer });
+            }
+
+            if !variants.iter().any(|variant: &AnswerVariant| variant.get_answer_state()) {
+                diagnostics.error(
+                    "no_correct_answer",
+                    variants_pointer.clone(),
+                    format!("У вопроса {:?} нет ни одного варианта с is_answer: true", title)
+                );
+            }
+
+            let answers_count = variants.iter().filter(|variant: &&AnswerVariant| variant.get_answer_state()).count();
+            if grading == Some(GradingMode::SingleChoice) && answers_count > 1 {
+                diagnostics.error(
+                    "single_choice_many_answers",
+                    variants_pointer,
+                    format!("У вопроса {:?} с одиночным выбором {} правильных вариантов", title, answers_count)
+                );
+            }
+
+            let question = Question { uuid, title, category, weight, difficulty, grading, variants };
+            if questions.iter().any(|other: &Question| other.title == question.title) {
+                diagnostics.error(
+                    "duplicate_question",
+                    question_pointer,
+                    format!("Вопрос {:?} повторяется", question.get_title())
+                );
+                continue
+            }
+
+            if questions.contains(&question) {
+                diagnostics.error(
+                    "duplicate_question_uuid",
+                    pointer_push(&question_pointer, "uuid"),
+                    format!("uuid {} уже занят другим вопросом", question.get_uuid())
+                );
+                continue
+            }
+
+            questions.insert(question);
+        }
+
+        Ok(questions)
     }
 
-    fn parse_questions(value: &Value) -> HashSet<Question> {
-        serde_json::from_value::<HashSet<Question>>(value.clone())
-            .expect("Пасинг вопрос произошел неудачно")
+    /// Собирает все проблемы схемы: найденные при разборе файла и
+    /// перекрёстные ссылки между вакансиями, навыками и компаниями.
+    /// Указатели относятся к файлу в той версии формата, в которой он
+    /// загружен.
+    pub fn validate(&self) -> ValidationReport {
+        let mut report = self.validate_latest();
+
+        for diagnostic in &mut report.diagnostics {
+            diagnostic.pointer = source_pointer(self.source_version, &diagnostic.pointer);
+        }
+
+        report
     }
 
-    // Not use pls
-    pub fn delete_all_vacancies(&mut self) {
-        self.vacancies.remove(&Vacancy("глава".into()));
-        self.vacancies.insert(Vacancy("Hackme aaaa".into()));
+    /// Проблемы схемы с указателями в документ актуальной версии, как его
+    /// возвращает [`CoefficientScheme::to_document`]
+    pub(crate) fn validate_latest(&self) -> ValidationReport {
+        let mut report = self.diagnostics.clone();
+
+        let mut used_vacancies = HashSet::new();
 
-        println!("{:?}", self.vacancies);
+        for skill in &self.skills {
+            let skill_pointer = pointer_push("/skills", &skill.name);
+
+            if skill.get_vacancies_coefficient().is_empty() {
+                report.warning(
+                    "empty_skill",
+                    skill_pointer.clone(),
+                    format!("Навык {:?} не влияет ни на одну вакансию", skill.name)
+                );
+            }
+
+            for vac_coef in skill.get_vacancies_coefficient() {
+                let vacancy_name = match self.get_vacancy(vac_coef.get_vacancy_id()) {
+                    Some(vacancy) => &vacancy.0,
+                    None => continue
+                };
+                used_vacancies.insert(vac_coef.get_vacancy_id());
+
+                if !COEFFICIENT_RANGE.contains(&vac_coef.get_coefficient()) {
+                    report.error(
+                        "coefficient_out_of_range",
+                        pointer_push(&skill_pointer, vacancy_name),
+                        format!(
+                            "Коэффициент {} вне диапазона {}..={}",
+                            vac_coef.get_coefficient(),
+                            COEFFICIENT_RANGE.start(),
+                            COEFFICIENT_RANGE.end()
+                        )
+                    );
+                }
+            }
+        }
+
+        for (vacancy_id, vacancy) in self.vacancies.iter() {
+            if !used_vacancies.contains(&vacancy_id) {
+                report.warning(
+                    "unused_vacancy",
+                    "/vacancies",
+                    format!("Вакансия {:?} не связана ни с одним навыком", vacancy.0)
+                );
+            }
+        }
+
+        for company in &self.companies {
+            let mut positions = HashSet::new();
+            self.validate_job_level(
+                company.tree(),
+                &pointer_push("/companies", company.name()),
+                &mut positions,
+                &mut report
+            );
+        }
+
+        report
     }
 
-    pub fn get_vacancies(&self) -> &HashSet<Vacancy> {
+    fn validate_job_level(
+        &self,
+        level: &JobLevel,
+        pointer: &str,
+        positions: &mut HashSet<String>,
+        report: &mut ValidationReport
+    ) {
+        for (company_vacancy, target_vacancy) in level.label() {
+
+            if !self.vacancies.contains(target_vacancy) {
+                report.error(
+                    "unknown_vacancy",
+                    pointer_push(pointer, "vacancy"),
+                    format!("Вакансия {:?} не объявлена в /vacancies", target_vacancy)
+                );
+            }
+
+            if !positions.insert(company_vacancy.clone()) {
+                report.error(
+                    "duplicate_position",
+                    pointer_push(pointer, "position"),
+                    format!("Должность {:?} встречается в дереве компании повторно", company_vacancy)
+                );
+            }
+        }
+
+        if let Some(children) = &level.children {
+            let children_pointer = pointer_push(pointer, "children");
+
+            for (ind, child) in children.iter().enumerate() {
+                self.validate_job_level(
+                    child,
+                    &pointer_push(&children_pointer, &ind.to_string()),
+                    positions,
+                    report
+                );
+            }
+        }
+    }
+
+    pub fn get_vacancies(&self) -> &VacancyRegistry {
         &self.vacancies
     }
 
+    pub fn get_vacancy(&self, id: VacancyId) -> Option<&Vacancy> {
+        self.vacancies.get(id)
+    }
+
+    pub fn get_source_version(&self) -> u32 {
+        self.source_version
+    }
+
+    /// Навык с именами вакансий вместо идентификаторов. Коэффициенты
+    /// удалённых вакансий пропускаются.
+    pub fn get_skill_view(&self, skill: &Skill) -> SkillView {
+        let vacancies_coefficient = skill
+            .get_vacancies_coefficient()
+            .iter()
+            .filter_map(|vac_coef| {
+                self.get_vacancy(vac_coef.get_vacancy_id()).map(|vacancy| VacancyCoefficientView {
+                    vacancy: vacancy.clone(),
+                    coefficient: vac_coef.get_coefficient()
+                })
+            })
+            .collect();
+
+        SkillView {
+            name: skill.name.clone(),
+            vacancies_coefficient
+        }
+    }
+
     pub fn get_skills(&self) -> &HashSet<Skill> {
         &self.skills
     }
@@ -213,26 +814,195 @@ impl CoefficientScheme {
         &self.questions
     }
 
-    pub fn get_question_by_uuid(&self, uuid: &String) -> Question {
-        let q = self.questions.get(&uuid.clone());
+    pub fn get_question_by_uuid(&self, uuid: &str) -> Option<&Question> {
+        self.questions.get(uuid)
+    }
+}
+
+/// Общие заготовки тестов модулей
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use std::fs::File;
+    use serde_json::{json, Value};
+
+    use crate::{CoefficientScheme, LATEST_SCHEMA_VERSION};
+
+    /// Схема курса из корня репозитория
+    pub(crate) const SAMPLE_SCHEMA: &str = "../skill_coefficients.json";
 
-        q.unwrap().clone()
+    pub(crate) fn sample_scheme() -> CoefficientScheme {
+        CoefficientScheme::new(File::open(SAMPLE_SCHEMA).unwrap()).unwrap()
+    }
+
+    /// Документ схемы актуальной версии: разделы из `sections`, остальные пустые
+    pub(crate) fn document(sections: Value) -> Value {
+        let mut document = json!({
+            "schema_version": LATEST_SCHEMA_VERSION,
+            "vacancies": [],
+            "skills": {},
+            "companies": {},
+            "questions": []
+        });
+
+        for (section, value) in sections.as_object().expect("разделы схемы задаются объектом") {
+            document[section] = value.clone();
+        }
+
+        document
+    }
+
+    /// Схема из [`document`]
+    pub(crate) fn scheme(sections: Value) -> CoefficientScheme {
+        CoefficientScheme::from_value(&document(sections)).unwrap()
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::fs::File;
     use super::*;
 
     #[test]
     fn it_works() {
 
-        let f = File::open("../skill_coefficients.json").unwrap();
+        let schema = fixtures::sample_scheme();
+        let report = schema.validate();
+        assert!(!report.has_errors());
+    }
+
+    #[test]
+    fn validate_collects_all_problems() {
+
+        let schema = fixtures::scheme(json!({
+            "vacancies": [{ "name": "Team_Lead" }],
+            "skills": { "Ответственность": { "Team_Lead": 9, "Janitor": 1 } },
+            "companies": {
+                "Консалтинг": { "position": "Директор", "vacancy": "Manager" }
+            },
+            "questions": [{
+                "title": "Лидер — это:",
+                "variants": [
+                    { "content": "да", "is_answer": false },
+                    { "content": "да", "is_answer": false }
+                ]
+            }]
+        }));
+        let report = schema.validate();
+        let codes = report.errors().map(|d| d.code).collect::<HashSet<_>>();
+
+        assert!(codes.contains("unknown_vacancy"));
+        assert!(codes.contains("coefficient_out_of_range"));
+        assert!(codes.contains("duplicate_variant"));
+        assert!(codes.contains("no_correct_answer"));
+        assert!(report.errors().any(|d| d.pointer == "/companies/Консалтинг/vacancy"));
+        assert!(report.errors().any(|d| d.pointer == "/skills/Ответственность/Janitor"));
+    }
+
+    #[test]
+    fn migrates_v1_document() {
+
+        let json = json!({
+            "vacancies": ["Team_Lead", "Analytic"],
+            "skills": { "Ответственность": { "Team_Lead": 3 } },
+            "jobs": {
+                "jobs": ["Консалтинг"],
+                "companies": {
+                    "Консалтинг": {
+                        "label": { "Директор": "Team_Lead" },
+                        "children": [{ "label": { "Аналитик": "Analytic" } }]
+                    }
+                }
+            },
+            "questions": []
+        });
+
+        let schema = CoefficientScheme::from_value(&json).unwrap();
+        assert_eq!(schema.get_source_version(), 1);
+
+        let company = schema.get_companies().get(&"Консалтинг".to_owned()).unwrap();
+        assert_eq!(company.tree().get_iter().count(), 2);
+        assert_eq!(company.tree().label().get("Директор"), Some(&"Team_Lead".to_owned()));
+
+        let mut broken = json.clone();
+        broken["jobs"]["companies"]["Консалтинг"]["children"][0]["label"] = json!({});
+        match CoefficientScheme::from_value(&broken) {
+            Err(SchemaError::Unexpected { pointer, .. }) =>
+                assert_eq!(pointer, "/jobs/companies/Консалтинг/children/0/label"),
+            other => panic!("Ожидалась ошибка метки, получено {:?}", other)
+        }
+    }
+
+    #[test]
+    fn v1_errors_point_into_source_document() {
+
+        let schema = CoefficientScheme::from_value(&json!({
+            "vacancies": ["Team_Lead"],
+            "skills": { "Ответственность": { "Team_Lead": 3 } },
+            "jobs": {
+                "companies": {
+                    "Консалтинг": {
+                        "label": { "Директор": "Team_Lead" },
+                        "children": [{ "label": { "Уборщик": "Janitor" } }]
+                    }
+                }
+            },
+            "questions": []
+        })).unwrap();
+
+        let report = schema.validate();
+        assert!(report.errors().any(|d| d.pointer == "/jobs/companies/Консалтинг/children/0/label"));
+        assert!(report.errors().all(|d| !d.pointer.starts_with("/companies")));
+    }
 
-        let mut schema = CoefficientScheme::new(f).unwrap();
-        println!("\n{:#?}", schema);
-        //println!("\n{:?}", schema.skills.iter().next().unwrap().vacancies_coefficient[0].0.upgrade());
-        assert_eq!(4, 4);
+    #[test]
+    fn question_ids_are_stable_across_loads() {
+
+        let ids = |schema: &CoefficientScheme| schema
+            .get_questions()
+            .iter()
+            .map(|question| question.get_uuid().clone())
+            .collect::<HashSet<String>>();
+
+        let first = fixtures::sample_scheme();
+        assert_eq!(ids(&first), ids(&fixtures::sample_scheme()));
+
+        let question = first.get_questions().iter().next().unwrap();
+        assert_eq!(question.get_uuid(), &question_uuid(question.get_title()));
+        assert_eq!(first.get_question_by_uuid(question.get_uuid()).unwrap().get_title(), question.get_title());
+    }
+
+    #[test]
+    fn cloned_scheme_keeps_vacancy_links() {
+
+        let schema = fixtures::sample_scheme();
+
+        let mut cloned = schema.clone();
+        drop(schema);
+        cloned.add_vacancy("Hackme".into(), None).unwrap();
+
+        let skill = cloned.get_skills().get(&"Ответственность".to_owned()).unwrap().clone();
+        let view = cloned.get_skill_view(&skill);
+
+        assert_eq!(view.vacancies_coefficient.len(), skill.get_vacancies_coefficient().len());
+        assert!(view.vacancies_coefficient.iter().any(|v| v.vacancy.0 == "Team_Lead"));
+    }
+
+    #[test]
+    fn reports_pointer_of_invalid_coefficient() {
+
+        let json = fixtures::document(json!({
+            "vacancies": [{ "name": "Team_Lead" }],
+            "skills": { "Ответственность": { "Team_Lead": "3" } }
+        }));
+
+        match CoefficientScheme::from_value(&json) {
+            Err(err @ SchemaError::Unexpected { .. }) => {
+                let payload = serde_json::to_value(&err).unwrap();
+                assert_eq!(payload["code"], "schema_unexpected");
+                assert_eq!(payload["details"]["pointer"], "/skills/Ответственность/Team_Lead");
+                assert_eq!(payload["details"]["expected"], "целое число");
+                assert_eq!(payload["message"], err.to_string());
+            },
+            other => panic!("Ожидалась ошибка типа, получено {:?}", other)
+        }
     }
 }
@@ -0,0 +1,595 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::io::{self, Read, Seek, Write};
+use calamine::{Data, Reader, Xlsx};
+use rust_xlsxwriter::{Format, Workbook};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::gradebook::{csv_text, FORMULA_PREFIXES};
+use crate::{pointer_push, AuthoringError, CoefficientScheme, ToPayload, ValidationReport, VacancyDocument, COEFFICIENT_RANGE};
+
+/// Заголовок первого столбца матрицы
+const SKILL_HEADER: &str = "Навык";
+
+/// Excel открывает CSV как UTF-8 только с BOM в начале файла
+pub(crate) const UTF8_BOM: &str = "\u{feff}";
+
+/// Навык и его коэффициенты в порядке столбцов вакансий
+type MatrixRow = (String, Vec<Option<i64>>);
+
+#[derive(Debug)]
+pub enum MatrixError {
+    Io(io::Error),
+    Csv(csv::Error),
+    XlsxRead(calamine::XlsxError),
+    XlsxWrite(rust_xlsxwriter::XlsxError),
+    /// В файле нет ни одной строки или листа
+    EmptySheet
+}
+
+impl From<io::Error> for MatrixError {
+    fn from(value: io::Error) -> Self {
+        MatrixError::Io(value)
+    }
+}
+
+impl From<csv::Error> for MatrixError {
+    fn from(value: csv::Error) -> Self {
+        MatrixError::Csv(value)
+    }
+}
+
+impl From<calamine::XlsxError> for MatrixError {
+    fn from(value: calamine::XlsxError) -> Self {
+        MatrixError::XlsxRead(value)
+    }
+}
+
+impl From<rust_xlsxwriter::XlsxError> for MatrixError {
+    fn from(value: rust_xlsxwriter::XlsxError) -> Self {
+        MatrixError::XlsxWrite(value)
+    }
+}
+
+impl Display for MatrixError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MatrixError::Io(err) => write!(f, "Ошибка ввода-вывода матрицы: {}", err),
+            MatrixError::Csv(err) => write!(f, "Некорректный CSV: {}", err),
+            MatrixError::XlsxRead(err) => write!(f, "Некорректный XLSX: {}", err),
+            MatrixError::XlsxWrite(err) => write!(f, "Не удалось записать XLSX: {}", err),
+            MatrixError::EmptySheet => write!(f, "В файле нет матрицы коэффициентов")
+        }
+    }
+}
+
+impl std::error::Error for MatrixError {}
+
+impl ToPayload for MatrixError {
+    fn code_and_details(&self) -> (&'static str, Value) {
+        match self {
+            MatrixError::Io(err) => ("matrix_io", json!({ "kind": format!("{:?}", err.kind()) })),
+            MatrixError::Csv(err) => (
+                "matrix_csv",
+                json!({ "line": err.position().map(|position| position.line()) })
+            ),
+            MatrixError::XlsxRead(_) => ("matrix_xlsx", Value::Null),
+            MatrixError::XlsxWrite(_) => ("matrix_xlsx_write", Value::Null),
+            MatrixError::EmptySheet => ("matrix_empty", Value::Null)
+        }
+    }
+}
+
+/// Ячейка таблицы независимо от формата файла
+#[derive(Debug, Clone, PartialEq)]
+enum Cell {
+    Empty,
+    Number(f64),
+    Text(String)
+}
+
+impl Cell {
+    fn from_text(text: &str) -> Self {
+        let text = text.trim();
+
+        if text.is_empty() {
+            return Cell::Empty;
+        }
+
+        // Русский Excel пишет дробную часть через запятую
+        match text.replace(',', ".").parse::<f64>() {
+            Ok(number) => Cell::Number(number),
+            // Апостроф перед формулой ставит csv_text при экспорте
+            Err(_) => match text.strip_prefix('\'').filter(|rest| rest.starts_with(FORMULA_PREFIXES)) {
+                Some(rest) => Cell::Text(rest.to_owned()),
+                None => Cell::Text(text.to_owned())
+            }
+        }
+    }
+
+    fn from_data(data: &Data) -> Self {
+        match data {
+            Data::Empty => Cell::Empty,
+            Data::Int(number) => Cell::Number(*number as f64),
+            Data::Float(number) => Cell::Number(*number),
+            Data::String(text) => Cell::from_text(text),
+            other => Cell::Text(other.to_string())
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        *self == Cell::Empty
+    }
+
+    /// Текст ячейки заголовка или названия навыка
+    fn label(&self) -> String {
+        match self {
+            Cell::Empty => String::new(),
+            Cell::Number(number) => number.to_string(),
+            Cell::Text(text) => text.clone()
+        }
+    }
+}
+
+/// Адрес ячейки в нотации A1, строки и столбцы с нуля
+fn cell_address(row: usize, column: usize) -> String {
+    let mut letters = vec![];
+    let mut column = column + 1;
+
+    while column > 0 {
+        letters.push((b'A' + ((column - 1) % 26) as u8) as char);
+        column = (column - 1) / 26;
+    }
+
+    format!("{}{}", letters.iter().rev().collect::<String>(), row + 1)
+}
+
+/// Строки CSV с разделителем `delimiter`
+fn read_csv(content: &str, delimiter: u8) -> Result<Vec<Vec<Cell>>, MatrixError> {
+    let mut csv = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .delimiter(delimiter)
+        .from_reader(content.as_bytes());
+
+    let mut rows = vec![];
+    for record in csv.records() {
+        rows.push(record?.iter().map(Cell::from_text).collect::<Vec<Cell>>());
+    }
+
+    Ok(rows)
+}
+
+/// Матрица навык → вакансия, прочитанная из таблицы: строки — навыки,
+/// столбцы — вакансии. Ошибки в `report` не дают применить матрицу к схеме.
+#[derive(Debug, Clone, Serialize)]
+pub struct MatrixImport {
+    /// Вакансии в порядке столбцов
+    pub vacancies: Vec<String>,
+    pub skills: BTreeMap<String, BTreeMap<String, i64>>,
+    pub report: ValidationReport
+}
+
+impl MatrixImport {
+    /// Читает CSV с разделителем `;` или `,`: сначала `;`, а если заголовок
+    /// разобрался в один столбец — `,`. Вакансии, которых нет в схеме, —
+    /// ошибка, если не задан `add_vacancies`.
+    pub fn from_csv<R: Read>(mut reader: R, scheme: &CoefficientScheme, add_vacancies: bool) -> Result<Self, MatrixError> {
+
+        let mut content = String::new();
+        reader.read_to_string(&mut content)?;
+        let content = content.strip_prefix(UTF8_BOM).unwrap_or(&content);
+
+        let mut rows = read_csv(content, b';')?;
+        let header = rows.iter().find(|row| row.iter().any(|cell| !cell.is_empty()));
+        if header.is_none_or(|header| header.len() < 2) {
+            rows = read_csv(content, b',')?;
+        }
+
+        MatrixImport::from_rows(rows, scheme, add_vacancies)
+    }
+
+    /// Читает первый лист книги XLSX, вакансии — как в [`MatrixImport::from_csv`]
+    pub fn from_xlsx<R: Read + Seek>(reader: R, scheme: &CoefficientScheme, add_vacancies: bool) -> Result<Self, MatrixError> {
+
+        let mut workbook: Xlsx<R> = calamine::open_workbook_from_rs(reader)?;
+        let range = workbook.worksheet_range_at(0).ok_or(MatrixError::EmptySheet)??;
+
+        // Адреса ячеек считаются от A1, даже если лист начинается не с неё
+        let (height, width) = range.end().map_or((0, 0), |(row, column)| (row + 1, column + 1));
+        let rows = (0..height)
+            .map(|row| {
+                (0..width)
+                    .map(|column| range.get_value((row, column)).map_or(Cell::Empty, Cell::from_data))
+                    .collect::<Vec<Cell>>()
+            })
+            .collect::<Vec<Vec<Cell>>>();
+
+        MatrixImport::from_rows(rows, scheme, add_vacancies)
+    }
+
+    fn from_rows(rows: Vec<Vec<Cell>>, scheme: &CoefficientScheme, add_vacancies: bool) -> Result<Self, MatrixError> {
+
+        let mut rows = rows.into_iter().enumerate().filter(|(_, row)| row.iter().any(|cell| !cell.is_empty()));
+        let (header_row, header) = rows.next().ok_or(MatrixError::EmptySheet)?;
+
+        let mut report = ValidationReport::default();
+        let rows = rows.collect::<Vec<(usize, Vec<Cell>)>>();
+
+        // Номер столбца и вакансия; столбцы без заголовка и без данных пропускаются
+        let mut columns: Vec<(usize, String)> = vec![];
+        for (column, cell) in header.iter().enumerate().skip(1) {
+            let vacancy = cell.label();
+
+            if vacancy.is_empty() {
+                if rows.iter().any(|(_, row)| row.get(column).is_some_and(|cell| !cell.is_empty())) {
+                    report.error(
+                        "empty_header",
+                        "/vacancies",
+                        format!("Столбец {} с коэффициентами без названия вакансии", cell_address(header_row, column))
+                    );
+                }
+                continue
+            }
+
+            if columns.iter().any(|(_, name)| name == &vacancy) {
+                report.error(
+                    "duplicate_header",
+                    "/vacancies",
+                    format!("Вакансия {:?} повторяется в ячейке {}", vacancy, cell_address(header_row, column))
+                );
+                continue
+            }
+
+            if !scheme.get_vacancies().contains(&vacancy) {
+                if add_vacancies {
+                    report.warning(
+                        "unknown_vacancy",
+                        "/vacancies",
+                        format!(
+                            "Вакансии {:?} из ячейки {} нет в схеме, она будет добавлена",
+                            vacancy, cell_address(header_row, column)
+                        )
+                    );
+                } else {
+                    report.error(
+                        "unknown_vacancy",
+                        "/vacancies",
+                        format!(
+                            "Вакансии {:?} из ячейки {} нет в схеме",
+                            vacancy, cell_address(header_row, column)
+                        )
+                    );
+                }
+            }
+
+            columns.push((column, vacancy));
+        }
+
+        for (_, vacancy) in scheme.get_vacancies().iter() {
+            if !columns.iter().any(|(_, name)| name == &vacancy.0) {
+                report.warning(
+                    "missing_vacancy",
+                    "/vacancies",
+                    format!("Вакансии {:?} нет в матрице, она будет удалена из схемы", vacancy.0)
+                );
+            }
+        }
+
+        let mut skills = BTreeMap::new();
+        let mut seen_skills = HashSet::new();
+
+        for (row_index, row) in &rows {
+            let skill = row.first().map(Cell::label).unwrap_or_default();
+            let skill_pointer = pointer_push("/skills", &skill);
+
+            if skill.is_empty() {
+                report.error(
+                    "empty_skill",
+                    "/skills",
+                    format!("Строка {} с коэффициентами без названия навыка", row_index + 1)
+                );
+                continue
+            }
+
+            if !seen_skills.insert(skill.clone()) {
+                report.error(
+                    "duplicate_skill",
+                    skill_pointer,
+                    format!("Навык {:?} повторяется в ячейке {}", skill, cell_address(*row_index, 0))
+                );
+                continue
+            }
+
+            let mut coefficients = BTreeMap::new();
+            for (column, vacancy) in &columns {
+                let address = cell_address(*row_index, *column);
+                let pointer = pointer_push(&skill_pointer, vacancy);
+
+                let number = match row.get(*column).unwrap_or(&Cell::Empty) {
+                    Cell::Empty => continue,
+                    Cell::Number(number) => *number,
+                    Cell::Text(text) => {
+                        report.error(
+                            "not_a_number",
+                            pointer,
+                            format!("В ячейке {} {:?} вместо коэффициента", address, text)
+                        );
+                        continue
+                    }
+                };
+
+                if number.fract() != 0.0 {
+                    report.error(
+                        "not_a_number",
+                        pointer,
+                        format!("В ячейке {} дробное число {}, коэффициент должен быть целым", address, number)
+                    );
+                    continue
+                }
+
+                let coefficient = number as i64;
+                if !COEFFICIENT_RANGE.contains(&coefficient) {
+                    report.error(
+                        "coefficient_out_of_range",
+                        pointer,
+                        format!(
+                            "Коэффициент {} в ячейке {} вне диапазона {}..={}",
+                            coefficient, address, COEFFICIENT_RANGE.start(), COEFFICIENT_RANGE.end()
+                        )
+                    );
+                    continue
+                }
+
+                coefficients.insert(vacancy.clone(), coefficient);
+            }
+
+            skills.insert(skill, coefficients);
+        }
+
+        Ok(Self {
+            vacancies: columns.into_iter().map(|(_, vacancy)| vacancy).collect(),
+            skills,
+            report
+        })
+    }
+}
+
+impl CoefficientScheme {
+    /// Заменяет разделы `vacancies` и `skills` матрицей. Описания оставшихся
+    /// вакансий сохраняются, новые вакансии матрица добавляет, только если
+    /// прочитана с `add_vacancies`. Вакансии, которых нет в матрице, удаляются:
+    /// если на них ссылаются должности компаний, правка не применяется.
+    pub fn set_matrix(&mut self, matrix: &MatrixImport) -> Result<(), AuthoringError> {
+
+        if matrix.report.has_errors() {
+            return Err(AuthoringError::Invalid(matrix.report.clone()));
+        }
+
+        self.edit(|document| {
+            document.vacancies = matrix.vacancies
+                .iter()
+                .map(|name| VacancyDocument {
+                    name: name.clone(),
+                    description: document.vacancies
+                        .iter()
+                        .find(|vacancy| &vacancy.name == name)
+                        .and_then(|vacancy| vacancy.description.clone())
+                })
+                .collect();
+            document.skills = matrix.skills.clone();
+
+            Ok(())
+        })
+    }
+
+    /// Матрица схемы: заголовок и строки навыков в алфавитном порядке.
+    /// Отсутствующий коэффициент — пустая ячейка.
+    fn matrix_rows(&self) -> (Vec<String>, Vec<MatrixRow>) {
+        let document = self.to_document();
+
+        let vacancies = document.vacancies.into_iter().map(|vacancy| vacancy.name).collect::<Vec<String>>();
+        let rows = document.skills
+            .into_iter()
+            .map(|(skill, coefficients)| {
+                let cells = vacancies.iter().map(|vacancy| coefficients.get(vacancy).copied()).collect();
+                (skill, cells)
+            })
+            .collect();
+
+        (vacancies, rows)
+    }
+
+    /// Записывает матрицу в CSV с разделителем `;`, как у русского Excel.
+    /// Файл читает [`MatrixImport::from_csv`].
+    pub fn write_matrix_csv<W: Write>(&self, mut writer: W) -> Result<(), MatrixError> {
+        writer.write_all(UTF8_BOM.as_bytes())?;
+
+        let (vacancies, rows) = self.matrix_rows();
+        let mut csv = csv::WriterBuilder::new().delimiter(b';').from_writer(writer);
+
+        csv.write_record(std::iter::once(SKILL_HEADER.to_owned()).chain(vacancies.iter().map(|vacancy| csv_text(vacancy))))?;
+
+        for (skill, cells) in rows {
+            csv.write_record(
+                std::iter::once(csv_text(&skill))
+                    .chain(cells.into_iter().map(|cell| cell.map_or(String::new(), |coefficient| coefficient.to_string())))
+            )?;
+        }
+
+        csv.flush()?;
+
+        Ok(())
+    }
+
+    /// Книга XLSX с матрицей на первом листе
+    pub fn matrix_xlsx(&self) -> Result<Vec<u8>, MatrixError> {
+        let (vacancies, rows) = self.matrix_rows();
+
+        let mut workbook = Workbook::new();
+        let bold = Format::new().set_bold();
+        let sheet = workbook.add_worksheet();
+        sheet.set_name("Матрица")?;
+
+        sheet.write_string_with_format(0, 0, SKILL_HEADER, &bold)?;
+        for (column, vacancy) in vacancies.iter().enumerate() {
+            sheet.write_string_with_format(0, column as u16 + 1, vacancy, &bold)?;
+        }
+
+        for (row, (skill, cells)) in rows.iter().enumerate() {
+            sheet.write_string(row as u32 + 1, 0, skill)?;
+
+            for (column, cell) in cells.iter().enumerate() {
+                if let Some(coefficient) = cell {
+                    sheet.write_number(row as u32 + 1, column as u16 + 1, *coefficient as f64)?;
+                }
+            }
+        }
+
+        sheet.set_column_width(0, 30)?;
+        sheet.set_freeze_panes(1, 1)?;
+
+        Ok(workbook.save_to_buffer()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures;
+    use super::*;
+
+    fn scheme() -> CoefficientScheme {
+        fixtures::scheme(json!({
+            "vacancies": [{ "name": "Team_Lead", "description": "Руководит командой" }, { "name": "Janitor" }],
+            "skills": { "Ответственность": { "Team_Lead": 3, "Janitor": 1 }, "Пунктуальность": { "Janitor": 2 } },
+            "companies": { "Фирма": { "position": "Директор", "vacancy": "Team_Lead" } }
+        }))
+    }
+
+    #[test]
+    fn csv_matrix_round_trip() {
+
+        let scheme = scheme();
+
+        let mut csv = vec![];
+        scheme.write_matrix_csv(&mut csv).unwrap();
+        let imported = MatrixImport::from_csv(csv.as_slice(), &scheme, false).unwrap();
+
+        assert!(imported.report.diagnostics.is_empty());
+        assert_eq!(imported.vacancies, vec!["Team_Lead", "Janitor"]);
+        assert_eq!(imported.skills, scheme.to_document().skills);
+    }
+
+    #[test]
+    fn xlsx_matrix_round_trip() {
+
+        let scheme = scheme();
+
+        let xlsx = scheme.matrix_xlsx().unwrap();
+        let imported = MatrixImport::from_xlsx(io::Cursor::new(xlsx), &scheme, false).unwrap();
+
+        assert_eq!(imported.skills, scheme.to_document().skills);
+    }
+
+    #[test]
+    fn matrix_import_reports_bad_cells() {
+
+        let mut scheme = scheme();
+
+        // Таблица из русского Excel: `;` и пустые ячейки
+        let sheet = "Навык;Team_Lead;Analytic;\nОтветственность;4;abc;\nПунктуальность;2,5;9;\n;;;\nОтветственность;1;;\n";
+        let imported = MatrixImport::from_csv(sheet.as_bytes(), &scheme, true).unwrap();
+        let codes = imported.report.diagnostics.iter().map(|d| d.code).collect::<Vec<&str>>();
+
+        assert_eq!(
+            codes,
+            vec!["unknown_vacancy", "missing_vacancy", "not_a_number", "not_a_number", "coefficient_out_of_range", "duplicate_skill"]
+        );
+        assert!(imported.report.errors().any(|d| d.pointer == "/skills/Ответственность/Analytic" && d.message.contains("C2")));
+        assert!(matches!(scheme.set_matrix(&imported), Err(AuthoringError::Invalid(_))));
+    }
+
+    #[test]
+    fn matrix_import_falls_back_to_comma_delimiter() {
+
+        let sheet = "Навык,Team_Lead,Analytic\nОтветственность,4,2\n";
+        let imported = MatrixImport::from_csv(sheet.as_bytes(), &scheme(), true).unwrap();
+
+        assert_eq!(imported.vacancies, vec!["Team_Lead", "Analytic"]);
+        assert!(!imported.report.has_errors());
+    }
+
+    #[test]
+    fn quoted_comma_keeps_semicolon_delimiter() {
+
+        // Запятая в заголовке в кавычках не делает `,` разделителем
+        let sheet = "Навык;\"Team_Lead, старший\";Team_Lead\nОтветственность;;3\n";
+        let imported = MatrixImport::from_csv(sheet.as_bytes(), &scheme(), true).unwrap();
+
+        assert_eq!(imported.vacancies, vec!["Team_Lead, старший", "Team_Lead"]);
+    }
+
+    #[test]
+    fn new_vacancy_requires_add_vacancies() {
+
+        let mut scheme = scheme();
+
+        // Без add_vacancies новая вакансия — ошибка, а не молчаливое добавление
+        let sheet = "Навык,Team_Lead,Analytic\nОтветственность,4,2\n";
+        let imported = MatrixImport::from_csv(sheet.as_bytes(), &scheme, false).unwrap();
+
+        assert!(imported.report.errors().any(|d| d.code == "unknown_vacancy"));
+        assert!(matches!(scheme.set_matrix(&imported), Err(AuthoringError::Invalid(_))));
+    }
+
+    #[test]
+    fn set_matrix_replaces_skills_and_keeps_descriptions() {
+
+        let mut scheme = scheme();
+
+        // Janitor пропадает из матрицы, Team_Lead нужна компании и остаётся
+        let sheet = "Навык,Team_Lead,Analytic\nОтветственность,4,2\n";
+        let imported = MatrixImport::from_csv(sheet.as_bytes(), &scheme, true).unwrap();
+        scheme.set_matrix(&imported).unwrap();
+
+        let document = scheme.to_document();
+        assert_eq!(document.vacancies.len(), 2);
+        assert_eq!(document.vacancies[0].description.as_deref(), Some("Руководит командой"));
+        assert_eq!(document.skills["Ответственность"]["Analytic"], 2);
+        assert!(!document.skills.contains_key("Пунктуальность"));
+    }
+
+    #[test]
+    fn set_matrix_refuses_to_drop_vacancy_in_use() {
+
+        let mut scheme = scheme();
+
+        let sheet = "Навык,Analytic\nОтветственность,4\n";
+        let imported = MatrixImport::from_csv(sheet.as_bytes(), &scheme, true).unwrap();
+
+        assert!(matches!(scheme.set_matrix(&imported), Err(AuthoringError::Invalid(_))));
+        assert!(scheme.get_vacancies().contains("Team_Lead"));
+    }
+
+    #[test]
+    fn matrix_csv_escapes_formulas() {
+
+        let scheme = fixtures::scheme(json!({
+            "vacancies": [{ "name": "=Team_Lead" }],
+            "skills": { "-Ответственность": { "=Team_Lead": 3 }, "Пунктуальность; точность": { "=Team_Lead": 2 } }
+        }));
+
+        let mut csv = vec![];
+        scheme.write_matrix_csv(&mut csv).unwrap();
+        let text = String::from_utf8(csv).unwrap();
+        assert_eq!(
+            text.strip_prefix(UTF8_BOM).unwrap(),
+            "Навык;'=Team_Lead\n'-Ответственность;3\n\"Пунктуальность; точность\";2\n"
+        );
+
+        let imported = MatrixImport::from_csv(text.as_bytes(), &scheme, false).unwrap();
+        assert!(imported.report.diagnostics.is_empty());
+        assert_eq!(imported.skills, scheme.to_document().skills);
+    }
+}
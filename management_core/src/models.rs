@@ -1,292 +1,407 @@
-use std::borrow::Borrow;
-use std::collections::{HashMap, HashSet};
-use std::fmt::{Debug, Formatter};
-use std::hash::{Hash, Hasher};
-use std::ptr::Unique;
-use std::vec::IntoIter;
-use serde::{Serialize, Serializer, Deserialize, Deserializer};
-use serde::ser::SerializeStruct;
-use uuid::Uuid;
-
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
-pub struct Vacancy(pub String);
-
-impl Hash for Vacancy {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        self.0.hash(state)
-    }
-}
-
-impl From<String> for Vacancy {
-    fn from(value: String) -> Self {
-        Vacancy(value)
-    }
-}
-
-#[derive(Debug, Clone, Serialize)]
-pub struct Skill {
-    pub name: String,
-    pub vacancies_coefficient: Vec<VacancyCoefficient>
-}
-
-impl Skill {
-    pub fn get_vacancies_coefficient(&self) -> &Vec<VacancyCoefficient> {
-        &self.vacancies_coefficient
-    }
-}
-
-impl Borrow<String> for Skill {
-    fn borrow(&self) -> &String {
-        &self.name
-    }
-}
-
-impl Hash for Skill {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        self.name.hash(state)
-    }
-}
-
-impl PartialEq for Skill {
-    fn eq(&self, other: &Self) -> bool {
-        self.name == other.name
-    }
-}
-
-impl Eq for Skill {}
-
-#[derive(Debug, Clone, Serialize, Deserialize, Hash, PartialEq, Eq)]
-pub struct Job(String);
-
-#[derive(Clone)]
-pub struct VacancyCoefficient(Unique<Vacancy>, i64);
-
-impl VacancyCoefficient {
-    pub fn new(vacancy: &Vacancy, coefficient: i64) -> Self {
-
-        // Безопасность вышла покурить
-        let v = vacancy as *const Vacancy;
-        let vacancy_ptr = v as *mut Vacancy;
-
-        Self(Unique::new(vacancy_ptr).unwrap(), coefficient)
-    }
-
-    pub fn get_vacancy_name(&self) -> String {
-        let vacancy_name = unsafe {
-            self.0.as_ref().0.clone()
-        };
-
-        return vacancy_name
-    }
-
-    pub fn get_coefficient(&self) -> i64 {
-        self.1
-    }
-}
-
-impl Debug for VacancyCoefficient {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-
-        let vacancy_ref = unsafe {
-            self.0.as_ref()
-        };
-
-        write!(f, "VacancyCoefficient({:?}, {})", vacancy_ref, self.1)
-    }
-}
-
-impl Serialize for VacancyCoefficient {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
-        let vacancy = unsafe{
-            self.0.as_ref()
-        };
-
-        let mut s = serializer.serialize_struct("VacancyCoefficient", 2)?;
-        s.serialize_field("vacancy", &vacancy)?;
-        s.serialize_field("coefficient", &self.1)?;
-        s.end()
-    }
-}
-
-#[derive(Debug, Clone, Deserialize)]
-pub struct JobLevel {
-    label: HashMap<String, String>,
-    children: Option<Vec<JobLevel>>,
-}
-
-impl JobLevel {
-    pub fn label(&self) -> &HashMap<String, String> {
-        &self.label
-    }
-
-    pub fn get_iter(&self) -> IntoIter<JobLevel> {
-        let mut v_all_children = vec![];
-        v_all_children.push(self.clone());
-
-        if let Some(levels) = &self.children {
-            for level in levels {
-                let tmp_levels = level.get_iter();
-                v_all_children.extend(tmp_levels);
-            }
-        }
-
-        return v_all_children.into_iter()
-    }
-
-}
-
-
-impl Serialize for JobLevel {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
-
-        let mut s = serializer
-            .serialize_struct("JobLevel", 2)?;
-
-        s.serialize_field("label", &self.label
-            .iter()
-            .next()
-            .expect("JobLevel не содержит label")
-            .0
-        )?;
-
-        if let Some(children) = &self.children {
-            s.serialize_field("children", children)?;
-        }
-
-        s.end()
-    }
-}
-
-impl IntoIterator for JobLevel {
-    type Item = JobLevel;
-    type IntoIter = IntoIter<JobLevel>;
-
-    fn into_iter(self) -> Self::IntoIter {
-        let self_cl = self.clone();
-
-        if let Some(levels) = self.children {
-
-            let children_iter = levels.into_iter();
-            //levels.push(self_cl);
-            return [self_cl.into_iter(), children_iter].into_iter().flatten().collect::<Vec<JobLevel>>().into_iter()
-        }
-
-        Vec::new().into_iter()
-    }
-}
-
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Company {
-    pub name: String,
-    pub tree: JobLevel
-}
-
-impl Company {
-    pub fn name(&self) -> &String {
-        &self.name
-    }
-    pub fn tree(&self) -> &JobLevel {
-        &self.tree
-    }
-}
-
-impl Hash for Company {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        self.name.hash(state)
-    }
-}
-
-impl PartialEq for Company {
-    fn eq(&self, other: &Self) -> bool {
-        self.name.eq(&other.name)
-    }
-}
-
-impl Eq for Company {}
-
-impl Borrow<String> for Company {
-    fn borrow(&self) -> &String {
-        &self.name
-    }
-}
-
-fn create_string_uuid() -> String {
-    Uuid::new_v4().to_string()
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Question {
-    #[serde(default = "create_string_uuid")]
-    uuid: String,
-    title: String,
-    variants: HashSet<AnswerVariant>
-}
-
-impl Question {
-
-    pub fn get_uuid(&self) -> &String {
-        &self.uuid
-    }
-
-    pub fn get_variants(&self) -> &HashSet<AnswerVariant> {
-        &self.variants
-    }
-
-    pub fn get_title(&self) -> &String {
-        &self.title
-    }
-}
-
-impl Hash for Question {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        self.uuid.hash(state)
-    }
-}
-
-impl PartialEq for Question {
-    fn eq(&self, other: &Self) -> bool {
-        self.title.eq(&other.title)
-    }
-}
-
-impl Eq for Question {}
-
-impl Borrow<String> for Question {
-    fn borrow(&self) -> &String {
-        &self.uuid
-    }
-}
-
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct AnswerVariant {
-    content: String,
-    is_answer: bool
-}
-
-impl AnswerVariant {
-    pub fn get_answer_state(&self) -> bool {
-        self.is_answer
-    }
-}
-
-impl Hash for AnswerVariant {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        self.content.hash(state)
-    }
-}
-
-impl PartialEq for AnswerVariant {
-    fn eq(&self, other: &Self) -> bool {
-        self.content.eq(&other.content)
-    }
-}
-
-impl Eq for AnswerVariant {}
-
-impl Borrow<String> for AnswerVariant {
-    fn borrow(&self) -> &String {
-        &self.content
-    }
-}
\ No newline at end of file
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
+use std::vec::IntoIter;
+use serde::{Serialize, Serializer, Deserialize};
+use serde::ser::{Error, SerializeStruct};
+use uuid::Uuid;
+
+use crate::GradingMode;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Vacancy(pub String);
+
+impl Hash for Vacancy {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state)
+    }
+}
+
+impl From<String> for Vacancy {
+    fn from(value: String) -> Self {
+        Vacancy(value)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Skill {
+    pub name: String,
+    pub vacancies_coefficient: Vec<VacancyCoefficient>
+}
+
+impl Skill {
+    pub fn get_vacancies_coefficient(&self) -> &Vec<VacancyCoefficient> {
+        &self.vacancies_coefficient
+    }
+}
+
+impl Borrow<String> for Skill {
+    fn borrow(&self) -> &String {
+        &self.name
+    }
+}
+
+impl Hash for Skill {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.name.hash(state)
+    }
+}
+
+impl PartialEq for Skill {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl Eq for Skill {}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Hash, PartialEq, Eq)]
+pub struct Job(String);
+
+/// Стабильный идентификатор вакансии в [`VacancyRegistry`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct VacancyId(usize);
+
+/// Таблица вакансий схемы. Идентификаторы не переиспользуются после удаления,
+/// поэтому [`VacancyCoefficient`] остаются корректными при любых изменениях.
+#[derive(Debug, Clone, Default)]
+pub struct VacancyRegistry {
+    vacancies: Vec<Option<Vacancy>>,
+    index: HashMap<String, VacancyId>,
+    descriptions: HashMap<VacancyId, String>
+}
+
+impl VacancyRegistry {
+    /// Возвращает идентификатор вакансии, добавляя её при отсутствии
+    pub fn intern(&mut self, vacancy: Vacancy) -> VacancyId {
+        if let Some(id) = self.index.get(&vacancy.0) {
+            return *id;
+        }
+
+        let id = VacancyId(self.vacancies.len());
+        self.index.insert(vacancy.0.clone(), id);
+        self.vacancies.push(Some(vacancy));
+
+        id
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Vacancy> {
+        let id = self.index.remove(name)?;
+        self.descriptions.remove(&id);
+        self.vacancies[id.0].take()
+    }
+
+    pub fn set_description(&mut self, id: VacancyId, description: String) {
+        self.descriptions.insert(id, description);
+    }
+
+    pub fn get_description(&self, id: VacancyId) -> Option<&String> {
+        self.descriptions.get(&id)
+    }
+
+    pub fn get(&self, id: VacancyId) -> Option<&Vacancy> {
+        self.vacancies.get(id.0).and_then(|vacancy| vacancy.as_ref())
+    }
+
+    pub fn get_id(&self, name: &str) -> Option<VacancyId> {
+        self.index.get(name).copied()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.index.contains_key(name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (VacancyId, &Vacancy)> {
+        self.vacancies
+            .iter()
+            .enumerate()
+            .filter_map(|(ind, vacancy)| vacancy.as_ref().map(|v| (VacancyId(ind), v)))
+    }
+}
+
+impl Serialize for VacancyRegistry {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        serializer.collect_seq(self.iter().map(|(_, vacancy)| vacancy))
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VacancyCoefficient(VacancyId, i64);
+
+impl VacancyCoefficient {
+    pub fn new(vacancy: VacancyId, coefficient: i64) -> Self {
+        Self(vacancy, coefficient)
+    }
+
+    pub fn get_vacancy_id(&self) -> VacancyId {
+        self.0
+    }
+
+    pub fn get_coefficient(&self) -> i64 {
+        self.1
+    }
+}
+
+/// Навык с разрешёнными именами вакансий, в том виде, в каком его ждёт фронтенд
+#[derive(Debug, Clone, Serialize)]
+pub struct SkillView {
+    pub name: String,
+    pub vacancies_coefficient: Vec<VacancyCoefficientView>
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VacancyCoefficientView {
+    pub vacancy: Vacancy,
+    pub coefficient: i64
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct JobLevel {
+    pub(crate) label: HashMap<String, String>,
+    pub(crate) children: Option<Vec<JobLevel>>,
+}
+
+impl JobLevel {
+    pub fn label(&self) -> &HashMap<String, String> {
+        &self.label
+    }
+
+    pub fn get_iter(&self) -> IntoIter<JobLevel> {
+        let mut v_all_children = vec![];
+        v_all_children.push(self.clone());
+
+        if let Some(levels) = &self.children {
+            for level in levels {
+                let tmp_levels = level.get_iter();
+                v_all_children.extend(tmp_levels);
+            }
+        }
+
+        v_all_children.into_iter()
+    }
+
+}
+
+
+impl Serialize for JobLevel {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+
+        let label = self.label
+            .iter()
+            .next()
+            .ok_or_else(|| S::Error::custom("JobLevel не содержит label"))?;
+
+        let mut s = serializer
+            .serialize_struct("JobLevel", 2)?;
+
+        s.serialize_field("label", &label.0)?;
+
+        if let Some(children) = &self.children {
+            s.serialize_field("children", children)?;
+        }
+
+        s.end()
+    }
+}
+
+impl IntoIterator for JobLevel {
+    type Item = JobLevel;
+    type IntoIter = IntoIter<JobLevel>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let self_cl = self.clone();
+
+        if let Some(levels) = self.children {
+
+            let children_iter = levels.into_iter();
+            //levels.push(self_cl);
+            return [self_cl.into_iter(), children_iter].into_iter().flatten().collect::<Vec<JobLevel>>().into_iter()
+        }
+
+        Vec::new().into_iter()
+    }
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Company {
+    pub name: String,
+    pub tree: JobLevel
+}
+
+impl Company {
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+    pub fn tree(&self) -> &JobLevel {
+        &self.tree
+    }
+}
+
+impl Hash for Company {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.name.hash(state)
+    }
+}
+
+impl PartialEq for Company {
+    fn eq(&self, other: &Self) -> bool {
+        self.name.eq(&other.name)
+    }
+}
+
+impl Eq for Company {}
+
+impl Borrow<String> for Company {
+    fn borrow(&self) -> &String {
+        &self.name
+    }
+}
+
+/// Пространство имён UUIDv5 для вопросов схемы
+const QUESTION_NAMESPACE: Uuid = Uuid::from_u128(0x6f1c_2a4e_93b7_4d0a_8c55_1e2f_7a9d_3b60);
+
+/// Идентификатор вопроса без явного `uuid` в схеме: UUIDv5 от заголовка,
+/// поэтому он не меняется между запусками
+pub fn question_uuid(title: &str) -> String {
+    Uuid::new_v5(&QUESTION_NAMESPACE, title.as_bytes()).to_string()
+}
+
+/// Идентификатор варианта без явного `id` в схеме: UUIDv5 от uuid вопроса
+/// и текста варианта, поэтому он не зависит от порядка вариантов
+pub fn variant_uuid(question_uuid: &str, content: &str) -> String {
+    Uuid::new_v5(&QUESTION_NAMESPACE, format!("{}/{}", question_uuid, content).as_bytes()).to_string()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Question {
+    pub(crate) uuid: String,
+    pub(crate) title: String,
+    #[serde(default)]
+    pub(crate) category: Option<String>,
+    #[serde(default)]
+    pub(crate) weight: Option<f64>,
+    #[serde(default)]
+    pub(crate) difficulty: Option<u8>,
+    #[serde(default)]
+    pub(crate) grading: Option<GradingMode>,
+    /// Варианты в том порядке, в котором они записаны в схеме
+    pub(crate) variants: Vec<AnswerVariant>
+}
+
+impl Question {
+
+    pub fn get_uuid(&self) -> &String {
+        &self.uuid
+    }
+
+    pub fn get_variants(&self) -> &[AnswerVariant] {
+        &self.variants
+    }
+
+    pub fn get_variant(&self, id: &str) -> Option<&AnswerVariant> {
+        self.variants.iter().find(|variant| variant.id == id)
+    }
+
+    pub fn get_title(&self) -> &String {
+        &self.title
+    }
+
+    pub fn get_category(&self) -> Option<&String> {
+        self.category.as_ref()
+    }
+
+    pub fn get_weight(&self) -> Option<f64> {
+        self.weight
+    }
+
+    pub fn get_difficulty(&self) -> Option<u8> {
+        self.difficulty
+    }
+
+    /// Режим из схемы, а если он не задан — одиночный выбор для вопроса
+    /// с одним правильным вариантом и точное совпадение для остальных
+    pub fn get_grading_mode(&self) -> GradingMode {
+        self.grading.unwrap_or_else(|| {
+            if self.variants.iter().filter(|variant| variant.is_answer).count() == 1 {
+                GradingMode::SingleChoice
+            } else {
+                GradingMode::ExactMatch
+            }
+        })
+    }
+}
+
+impl Hash for Question {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.uuid.hash(state)
+    }
+}
+
+/// Вопросы сравниваются по uuid, как и хешируются: `HashSet<Question>`
+/// ищет их по uuid через `Borrow<String>` и `Borrow<str>`
+impl PartialEq for Question {
+    fn eq(&self, other: &Self) -> bool {
+        self.uuid.eq(&other.uuid)
+    }
+}
+
+impl Eq for Question {}
+
+impl Borrow<String> for Question {
+    fn borrow(&self) -> &String {
+        &self.uuid
+    }
+}
+
+impl Borrow<str> for Question {
+    fn borrow(&self) -> &str {
+        &self.uuid
+    }
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnswerVariant {
+    pub(crate) id: String,
+    /// Текст без буквы варианта: буквы расставляются при выдаче теста
+    pub(crate) content: String,
+    pub(crate) is_answer: bool
+}
+
+impl AnswerVariant {
+    pub fn get_id(&self) -> &String {
+        &self.id
+    }
+
+    pub fn get_content(&self) -> &String {
+        &self.content
+    }
+
+    pub fn get_answer_state(&self) -> bool {
+        self.is_answer
+    }
+}
+
+impl Hash for AnswerVariant {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state)
+    }
+}
+
+impl PartialEq for AnswerVariant {
+    fn eq(&self, other: &Self) -> bool {
+        self.id.eq(&other.id)
+    }
+}
+
+impl Eq for AnswerVariant {}
\ No newline at end of file
@@ -0,0 +1,287 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::{CoefficientScheme, SchemaError};
+
+/// Расширение файлов пакетов в каталоге пакетов
+pub const PACK_EXTENSION: &str = "json";
+
+/// Сколько шестнадцатеричных знаков SHA-256 оставлять в версии пакета
+const VERSION_LENGTH: usize = 16;
+
+impl CoefficientScheme {
+    /// Отпечаток содержимого схемы: SHA-256 документа в актуальном формате.
+    /// Не зависит от форматирования файла и порядка ключей в нём.
+    pub fn fingerprint(&self) -> String {
+        let document = serde_json::to_vec(&self.to_document()).expect("документ схемы сериализуется в JSON");
+
+        sha256_hex(&document)
+    }
+
+    /// Версия пакета с этой схемой, как в [`SchemaPack::get_version`]
+    pub(crate) fn pack_version(&self) -> String {
+        self.fingerprint()[..VERSION_LENGTH].to_owned()
+    }
+}
+
+pub(crate) fn sha256_hex(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// Схема курса, загруженная из отдельного файла. Имя пакета — имя файла
+/// без расширения.
+#[derive(Debug, Clone)]
+pub struct SchemaPack {
+    name: String,
+    path: PathBuf,
+    version: String,
+    schema: CoefficientScheme
+}
+
+/// Краткие сведения о пакете для фронтенда
+#[derive(Debug, Clone, Serialize)]
+pub struct PackInfo {
+    pub name: String,
+    /// Начало отпечатка схемы, меняется с любой правкой содержимого
+    pub version: String,
+    pub is_default: bool,
+    pub vacancies: usize,
+    pub skills: usize,
+    pub companies: usize,
+    pub questions: usize
+}
+
+impl SchemaPack {
+    pub fn new(name: String, path: PathBuf, schema: CoefficientScheme) -> Self {
+        let version = schema.pack_version();
+
+        Self { name, path, version, schema }
+    }
+
+    /// Разбирает и проверяет файл пакета. Схема с ошибками валидации
+    /// отклоняется, предупреждения остаются в [`CoefficientScheme::validate`].
+    pub fn load(path: &Path) -> Result<Self, SchemaError> {
+
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .ok_or_else(|| SchemaError::Custom {
+                name: path.display().to_string(),
+                description: "Путь не указывает на файл".to_owned()
+            })?;
+
+        let schema = CoefficientScheme::new(File::open(path)?)?;
+
+        let report = schema.validate();
+        if report.has_errors() {
+            return Err(SchemaError::Invalid(report));
+        }
+
+        Ok(SchemaPack::new(name, path.to_path_buf(), schema))
+    }
+
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn get_path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn get_version(&self) -> &String {
+        &self.version
+    }
+
+    pub fn get_schema(&self) -> &CoefficientScheme {
+        &self.schema
+    }
+
+    /// Каталог дополнительных файлов пакета рядом с его схемой:
+    /// `schemas/it.json` → `schemas/it.assets`
+    pub fn get_assets_dir(&self) -> PathBuf {
+        self.path.with_extension("assets")
+    }
+}
+
+/// Загруженные пакеты схем по именам
+#[derive(Debug, Clone, Default)]
+pub struct SchemaPacks {
+    packs: BTreeMap<String, SchemaPack>,
+    /// Пакет для сессий, которым пакет не выбран. По умолчанию первый загруженный.
+    default: Option<String>
+}
+
+impl SchemaPacks {
+    /// Загружает все `*.json` каталога. Файлы, которые не удалось загрузить,
+    /// пропускаются и возвращаются вместе с ошибками.
+    pub fn load_dir(&mut self, dir: &Path) -> io::Result<Vec<(PathBuf, SchemaError)>> {
+
+        let mut paths = fs::read_dir(dir)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<io::Result<Vec<PathBuf>>>()?;
+        paths.retain(|path| path.is_file() && path.extension().is_some_and(|extension| extension == PACK_EXTENSION));
+        paths.sort();
+
+        let mut failed = vec![];
+
+        for path in paths {
+            match SchemaPack::load(&path) {
+                Ok(pack) => {
+                    self.insert(pack);
+                },
+                Err(err) => failed.push((path, err))
+            }
+        }
+
+        Ok(failed)
+    }
+
+    /// Добавляет пакет или заменяет пакет с тем же именем
+    pub fn insert(&mut self, pack: SchemaPack) -> Option<SchemaPack> {
+        if self.default.is_none() {
+            self.default = Some(pack.name.clone());
+        }
+
+        self.packs.insert(pack.name.clone(), pack)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&SchemaPack> {
+        self.packs.get(name)
+    }
+
+    pub fn get_default(&self) -> Option<&SchemaPack> {
+        self.default.as_ref().and_then(|name| self.packs.get(name))
+    }
+
+    pub fn set_default(&mut self, name: &str) -> bool {
+        if !self.packs.contains_key(name) {
+            return false;
+        }
+
+        self.default = Some(name.to_owned());
+
+        true
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.packs.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &SchemaPack> {
+        self.packs.values()
+    }
+
+    pub fn info(&self, pack: &SchemaPack) -> PackInfo {
+        PackInfo {
+            name: pack.name.clone(),
+            version: pack.version.clone(),
+            is_default: self.default.as_ref() == Some(&pack.name),
+            vacancies: pack.schema.get_vacancies().len(),
+            skills: pack.schema.get_skills().len(),
+            companies: pack.schema.get_companies().len(),
+            questions: pack.schema.get_questions().len()
+        }
+    }
+
+    pub fn infos(&self) -> Vec<PackInfo> {
+        self.iter().map(|pack| self.info(pack)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use crate::{fixtures, Page, ResultFilter, Storage, StorageError};
+    use super::*;
+
+    /// Каталог `packs_<name>_<pid>` с двумя пакетами, битым файлом и
+    /// посторонним файлом
+    fn packs_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("packs_{}_{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::copy(fixtures::SAMPLE_SCHEMA, dir.join("it.json")).unwrap();
+        fs::write(dir.join("retail.json"), serde_json::to_vec(&fixtures::document(json!({
+            "vacancies": [{ "name": "Кассир" }],
+            "skills": { "Внимательность": { "Кассир": 3 } },
+            "companies": { "Магазин": { "position": "Кассир", "vacancy": "Кассир" } }
+        }))).unwrap()).unwrap();
+        fs::write(dir.join("broken.json"), "{").unwrap();
+        fs::write(dir.join("notes.txt"), "не пакет").unwrap();
+
+        dir
+    }
+
+    #[test]
+    fn schema_packs_load_side_by_side() {
+
+        let dir = packs_dir("load");
+        let mut packs = SchemaPacks::default();
+        let failed = packs.load_dir(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(failed.len(), 1);
+        assert!(failed[0].0.ends_with("broken.json"));
+        assert_eq!(packs.iter().map(|pack| pack.get_name().as_str()).collect::<Vec<&str>>(), vec!["it", "retail"]);
+        assert_eq!(packs.get_default().unwrap().get_name(), "it");
+
+        let retail = packs.get("retail").unwrap();
+        assert_eq!(packs.info(retail).companies, 1);
+        assert!(retail.get_schema().get_companies().contains(&"Магазин".to_owned()));
+    }
+
+    #[test]
+    fn pack_version_ignores_formatting() {
+
+        let dir = packs_dir("version");
+        let mut packs = SchemaPacks::default();
+        packs.load_dir(&dir).unwrap();
+        let retail = packs.get("retail").unwrap();
+
+        let mut saved = retail.get_schema().clone();
+        saved.save(&dir.join("retail.json")).unwrap();
+        let reloaded = SchemaPack::load(&dir.join("retail.json")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(reloaded.get_version(), retail.get_version());
+        assert_ne!(retail.get_version(), packs.get("it").unwrap().get_version());
+    }
+
+    #[test]
+    fn default_pack_must_be_loaded() {
+
+        let dir = packs_dir("default");
+        let mut packs = SchemaPacks::default();
+        packs.load_dir(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(packs.set_default("retail"));
+        assert!(!packs.set_default("public"));
+        assert_eq!(packs.get_default().unwrap().get_name(), "retail");
+    }
+
+    #[test]
+    fn finished_session_keeps_its_pack() {
+
+        let retail = SchemaPack::new("retail".into(), "retail.json".into(), fixtures::sample_scheme());
+
+        let mut storage = Storage::open_in_memory().unwrap();
+        let student = storage.create_student("Петров", None).unwrap();
+        let session = storage.start_session(student.id).unwrap();
+        assert!(session.schema_pack.is_none());
+
+        storage.record_schema_pack(session.id, retail.get_name(), retail.get_version()).unwrap();
+        storage.finish_session(session.id).unwrap();
+        assert!(matches!(
+            storage.record_schema_pack(session.id, "it", "0"),
+            Err(StorageError::SessionFinished { .. })
+        ));
+
+        let page = storage.query_sessions(&ResultFilter::default(), &Page::default()).unwrap();
+        assert_eq!(page.items[0].schema_pack.as_deref(), Some("retail"));
+        assert_eq!(page.items[0].schema_version.as_ref(), Some(retail.get_version()));
+    }
+}
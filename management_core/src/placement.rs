@@ -0,0 +1,372 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::document::path_pointer;
+use crate::{CoefficientScheme, Company, ToPayload};
+
+/// Работник, поставленный студентом на должность в дереве компании.
+/// Создаётся только из проверенных данных через [`PlacedWorker::from_value`].
+#[derive(Debug, Clone, Serialize)]
+pub struct PlacedWorker {
+    pub id: u64,
+    pub name: String,
+    /// Качества, выбранные студентом для работника
+    pub qualities: Vec<String>,
+    /// Вакансии работника от наиболее к наименее подходящей
+    pub vacancies: Vec<String>
+}
+
+/// Данные работника в том виде, в каком их присылает фронтенд
+#[derive(Debug, Clone, Deserialize)]
+struct PlacedWorkerData {
+    id: u64,
+    name: String,
+    #[serde(default)]
+    qualities: Vec<String>,
+    vacancies: Vec<String>
+}
+
+impl PlacedWorker {
+    /// Разбирает и проверяет работника на должности `position` из JSON
+    /// фронтенда. Команды принимают работника как JSON и разбирают его сами,
+    /// чтобы фронтенд получил [`PlacementError`] с указателем на поле,
+    /// а не ошибку разбора аргументов.
+    pub fn from_value(position: &str, value: Value) -> Result<Self, PlacementError> {
+        let invalid = |pointer: &str, reason: String| PlacementError::InvalidWorker {
+            position: position.to_owned(),
+            pointer: pointer.to_owned(),
+            reason
+        };
+
+        let value: PlacedWorkerData = serde_path_to_error::deserialize(value)
+            .map_err(|err| invalid(&path_pointer(err.path()), err.into_inner().to_string()))?;
+
+        if value.name.trim().is_empty() {
+            return Err(invalid("/name", "пустое имя работника".to_owned()));
+        }
+
+        if value.vacancies.is_empty() {
+            return Err(invalid("/vacancies", "список вакансий работника пуст".to_owned()));
+        }
+
+        let mut seen = HashSet::new();
+        if !value.vacancies.iter().all(|vacancy| seen.insert(vacancy)) {
+            return Err(invalid("/vacancies", "вакансии работника повторяются".to_owned()));
+        }
+
+        Ok(Self {
+            id: value.id,
+            name: value.name,
+            qualities: value.qualities,
+            vacancies: value.vacancies
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum PlacementError {
+    UnknownCompany {
+        company: String
+    },
+    /// `pointer` указывает на поле внутри данных работника
+    InvalidWorker {
+        position: String,
+        pointer: String,
+        reason: String
+    },
+    UnknownVacancy {
+        position: String,
+        vacancy: String
+    }
+}
+
+impl Display for PlacementError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PlacementError::UnknownCompany { company } =>
+                write!(f, "Компания {:?} не найдена в схеме", company),
+            PlacementError::InvalidWorker { position, pointer, reason } =>
+                write!(f, "Некорректные данные работника на должности {:?} ({}): {}", position, pointer, reason),
+            PlacementError::UnknownVacancy { position, vacancy } =>
+                write!(f, "У работника на должности {:?} неизвестная вакансия {:?}", position, vacancy),
+        }
+    }
+}
+
+impl std::error::Error for PlacementError {}
+
+impl ToPayload for PlacementError {
+    fn code_and_details(&self) -> (&'static str, Value) {
+        match self {
+            PlacementError::UnknownCompany { company } =>
+                ("placement_unknown_company", json!({ "company": company })),
+            PlacementError::InvalidWorker { position, pointer, reason } =>
+                ("placement_invalid_worker", json!({ "position": position, "pointer": pointer, "reason": reason })),
+            PlacementError::UnknownVacancy { position, vacancy } =>
+                ("placement_unknown_vacancy", json!({ "position": position, "vacancy": vacancy })),
+        }
+    }
+}
+
+/// Сколько баллов даёт должность, если ожидаемая вакансия стоит у работника
+/// на позиции `rank` (с нуля)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RankReward {
+    /// 1 / (1 + rank)
+    Reciprocal,
+    /// Линейно от 1 до 0 на первых `depth` позициях
+    Linear { depth: usize },
+    /// Балл только за первую позицию
+    TopOnly
+}
+
+impl RankReward {
+    pub fn credit(&self, rank: usize) -> f64 {
+        match self {
+            RankReward::Reciprocal => 1.0 / (1.0 + rank as f64),
+            RankReward::Linear { depth } => {
+                if rank >= *depth {
+                    0.0
+                } else {
+                    1.0 - rank as f64 / *depth as f64
+                }
+            },
+            RankReward::TopOnly => if rank == 0 { 1.0 } else { 0.0 }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlacementConfig {
+    pub rank_reward: RankReward,
+    /// Вес задания в итоговой оценке: балл за идеальную расстановку
+    pub weight: f64,
+    /// Считать ли незаполненные должности нулём. Иначе они не влияют на балл.
+    pub count_missing: bool
+}
+
+impl Default for PlacementConfig {
+    fn default() -> Self {
+        Self {
+            rank_reward: RankReward::Reciprocal,
+            weight: 0.25,
+            count_missing: false
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlacementEntry {
+    /// Должность в дереве компании
+    pub position: String,
+    pub expected_vacancy: String,
+    pub worker: String,
+    /// Позиция ожидаемой вакансии в списке вакансий работника
+    pub rank: Option<usize>,
+    pub credit: f64
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlacementReport {
+    pub company: String,
+    pub entries: Vec<PlacementEntry>,
+    /// Должности, на которые никто не поставлен
+    pub missing: Vec<String>,
+    /// Поставленные работники, должностей которых нет в дереве компании
+    pub unknown_positions: Vec<String>,
+    /// Доля от идеальной расстановки, от 0 до 1
+    pub correctness: f64,
+    /// correctness * weight
+    pub score: f64
+}
+
+impl CoefficientScheme {
+    /// Проверяет, что компания существует и у работников только известные
+    /// вакансии, и оценивает расстановку
+    pub fn grade_placement(
+        &self,
+        company_name: &str,
+        placements: &HashMap<String, PlacedWorker>,
+        config: &PlacementConfig
+    ) -> Result<PlacementReport, PlacementError> {
+
+        let company = self
+            .get_companies()
+            .get(&company_name.to_owned())
+            .ok_or_else(|| PlacementError::UnknownCompany { company: company_name.to_owned() })?;
+
+        for (position, worker) in placements {
+            if let Some(vacancy) = worker.vacancies.iter().find(|vacancy| !self.get_vacancies().contains(vacancy)) {
+                return Err(PlacementError::UnknownVacancy {
+                    position: position.clone(),
+                    vacancy: vacancy.clone()
+                });
+            }
+        }
+
+        Ok(company.grade_placement(placements, config))
+    }
+}
+
+impl Company {
+    pub fn grade_placement(
+        &self,
+        placements: &HashMap<String, PlacedWorker>,
+        config: &PlacementConfig
+    ) -> PlacementReport {
+
+        let mut entries = vec![];
+        let mut missing = vec![];
+        let mut known_positions = vec![];
+
+        for job_level in self.tree().get_iter() {
+            let (position, expected_vacancy) = match job_level.label().iter().next() {
+                Some(label) => label,
+                None => continue
+            };
+            known_positions.push(position.clone());
+
+            let worker = match placements.get(position) {
+                Some(worker) => worker,
+                None => {
+                    missing.push(position.clone());
+                    continue
+                }
+            };
+
+            let rank = worker.vacancies
+                .iter()
+                .position(|vacancy| vacancy == expected_vacancy);
+
+            entries.push(PlacementEntry {
+                position: position.clone(),
+                expected_vacancy: expected_vacancy.clone(),
+                worker: worker.name.clone(),
+                rank,
+                credit: rank.map_or(0.0, |rank| config.rank_reward.credit(rank))
+            });
+        }
+
+        let mut unknown_positions = placements
+            .keys()
+            .filter(|position| !known_positions.contains(position))
+            .cloned()
+            .collect::<Vec<String>>();
+        unknown_positions.sort();
+
+        let graded = if config.count_missing {
+            entries.len() + missing.len()
+        } else {
+            entries.len()
+        };
+
+        let correctness = if graded == 0 {
+            0.0
+        } else {
+            entries.iter().map(|entry| entry.credit).sum::<f64>() / graded as f64
+        };
+
+        PlacementReport {
+            company: self.name().clone(),
+            entries,
+            missing,
+            unknown_positions,
+            correctness,
+            score: correctness * config.weight
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures;
+    use super::*;
+
+    fn schema() -> CoefficientScheme {
+        fixtures::scheme(json!({
+            "vacancies": [{ "name": "Team_Lead" }, { "name": "Analytic" }, { "name": "Programmer" }],
+            "companies": {
+                "Консалтинг": {
+                    "position": "Директор",
+                    "vacancy": "Team_Lead",
+                    "children": [
+                        { "position": "Аналитик", "vacancy": "Analytic" },
+                        { "position": "Программист", "vacancy": "Programmer" }
+                    ]
+                }
+            }
+        }))
+    }
+
+    fn placements() -> HashMap<String, PlacedWorker> {
+        HashMap::from([
+            ("Директор", json!({ "id": 0, "name": "Олег", "vacancies": ["Team_Lead", "Analytic"] })),
+            ("Аналитик", json!({ "id": 1, "name": "Ирина", "qualities": ["Спокойствие"], "vacancies": ["Programmer", "Analytic"] }))
+        ])
+            .into_iter()
+            .map(|(position, worker)| (position.to_owned(), PlacedWorker::from_value(position, worker).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn placement_report_explains_credit() {
+
+        let report = schema().grade_placement("Консалтинг", &placements(), &PlacementConfig::default()).unwrap();
+
+        assert_eq!(report.missing, vec!["Программист".to_owned()]);
+        assert_eq!(report.entries[1].rank, Some(1));
+        assert_eq!(report.entries[1].credit, 0.5);
+        assert_eq!(report.correctness, 0.75);
+        assert_eq!(report.score, 0.75 * 0.25);
+    }
+
+    #[test]
+    fn strict_config_counts_missing_positions() {
+
+        let schema = schema();
+        let company = schema.get_companies().get(&"Консалтинг".to_owned()).unwrap();
+        let strict = PlacementConfig { weight: 1.0, count_missing: true, ..PlacementConfig::default() };
+
+        assert_eq!(company.grade_placement(&placements(), &strict).score, 0.5);
+    }
+
+    #[test]
+    fn placed_worker_requires_vacancies() {
+
+        let malformed = json!({ "id": 2, "name": "Пётр", "vacancies": [] });
+        let payload = PlacedWorker::from_value("Программист", malformed).unwrap_err().to_payload();
+
+        assert_eq!(payload.code, "placement_invalid_worker");
+        assert_eq!(payload.details["position"], "Программист");
+        assert_eq!(payload.details["pointer"], "/vacancies");
+    }
+
+    #[test]
+    fn placement_rejects_unknown_vacancy() {
+
+        let mut unknown = placements();
+        unknown.get_mut("Директор").unwrap().vacancies.push("Janitor".into());
+
+        assert!(matches!(
+            schema().grade_placement("Консалтинг", &unknown, &PlacementConfig::default()),
+            Err(PlacementError::UnknownVacancy { vacancy, .. }) if vacancy == "Janitor"
+        ));
+    }
+
+    #[test]
+    fn placed_worker_reports_wrong_type() {
+
+        let worker = json!({ "id": "семь", "name": "Олег", "vacancies": ["Team_Lead"] });
+        match PlacedWorker::from_value("Директор", worker) {
+            Err(PlacementError::InvalidWorker { position, pointer, .. }) => {
+                assert_eq!(position, "Директор");
+                assert_eq!(pointer, "/id");
+            }
+            other => panic!("Ожидалась ошибка работника, получено {:?}", other)
+        }
+    }
+}
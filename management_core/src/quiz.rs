@@ -0,0 +1,672 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{AnswerVariant, AssessmentSession, CoefficientScheme, Question, QuizAnswerRecord, ToPayload};
+
+/// Вес вопроса, для которого он не задан в схеме
+pub const DEFAULT_QUESTION_WEIGHT: f64 = 1.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GradingMode {
+    /// Выбран ровно один вариант, и он правильный
+    SingleChoice,
+    /// Выбраны все правильные варианты и ни одного лишнего
+    ExactMatch,
+    /// Доля выбранных правильных вариантов минус доля выбранных неправильных
+    PartialCredit
+}
+
+/// Буквы вариантов при выдаче теста. Дальше варианты нумеруются цифрами.
+const VARIANT_LETTERS: &[char] = &['а', 'б', 'в', 'г', 'д', 'е', 'ж', 'з', 'и', 'к'];
+
+fn variant_letter(ind: usize) -> String {
+    VARIANT_LETTERS
+        .get(ind)
+        .map_or_else(|| (ind + 1).to_string(), |letter| letter.to_string())
+}
+
+/// Вариант ответа без признака правильности. Студент отвечает по `id`.
+#[derive(Debug, Clone, Serialize)]
+pub struct VariantView {
+    pub id: String,
+    /// Буква по месту варианта в выданном тесте: «а», «б», …
+    pub letter: String,
+    pub content: String
+}
+
+/// Вопрос в том виде, в каком его видит студент: без признака правильности
+#[derive(Debug, Clone, Serialize)]
+pub struct QuestionView {
+    pub uuid: String,
+    pub title: String,
+    pub category: Option<String>,
+    pub difficulty: Option<u8>,
+    /// Чтобы интерфейс знал, можно ли выбрать несколько вариантов
+    pub grading: GradingMode,
+    pub variants: Vec<VariantView>
+}
+
+impl QuestionView {
+    /// Варианты в порядке схемы или перемешанные, если передан `rng`
+    pub fn render(question: &Question, rng: Option<&mut StdRng>) -> Self {
+        let mut variants = question.variants.iter().collect::<Vec<&AnswerVariant>>();
+        if let Some(rng) = rng {
+            variants.shuffle(rng);
+        }
+
+        Self {
+            uuid: question.uuid.clone(),
+            title: question.title.clone(),
+            category: question.category.clone(),
+            difficulty: question.difficulty,
+            grading: question.get_grading_mode(),
+            variants: variants
+                .into_iter()
+                .enumerate()
+                .map(|(ind, variant)| VariantView {
+                    id: variant.id.clone(),
+                    letter: variant_letter(ind),
+                    content: variant.content.clone()
+                })
+                .collect()
+        }
+    }
+}
+
+impl From<&Question> for QuestionView {
+    fn from(question: &Question) -> Self {
+        QuestionView::render(question, None)
+    }
+}
+
+/// Сколько вопросов взять из категории и уровня сложности.
+/// Пустые `category` и `difficulty` означают «любой».
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlueprintSection {
+    #[serde(default)]
+    pub category: Option<String>,
+    #[serde(default)]
+    pub difficulty: Option<u8>,
+    pub count: usize
+}
+
+/// Состав теста по разделам. Разделы заполняются по порядку, вопрос
+/// не попадает в тест дважды, поэтому раздел «любой» стоит ставить последним.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuizBlueprint {
+    pub sections: Vec<BlueprintSection>,
+    /// Перемешивать ли варианты ответов. Порядок зависит от seed теста.
+    #[serde(default)]
+    pub shuffle_variants: bool
+}
+
+impl Default for QuizBlueprint {
+    /// 15 вопросов из всего банка, как раньше
+    fn default() -> Self {
+        Self {
+            sections: vec![BlueprintSection { category: None, difficulty: None, count: 15 }],
+            shuffle_variants: false
+        }
+    }
+}
+
+/// Варианты, выбранные студентом в одном вопросе
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubmittedAnswer {
+    pub question_uuid: String,
+    /// id выбранных вариантов
+    pub answers: Vec<String>
+}
+
+/// Оценка одного вопроса. Правильные варианты не раскрываются.
+#[derive(Debug, Clone, Serialize)]
+pub struct QuestionGrade {
+    pub question_uuid: String,
+    pub title: String,
+    pub grading: GradingMode,
+    pub selected: Vec<String>,
+    /// Доля балла от 0 до 1
+    pub credit: f64,
+    pub weight: f64,
+    /// credit * weight
+    pub points: f64
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QuizGrade {
+    /// Оценка по каждому выданному вопросу. Неотвеченные получают 0.
+    pub questions: Vec<QuestionGrade>,
+    pub points: f64,
+    /// Сумма весов выданных вопросов
+    pub max_points: f64,
+    /// points / max_points
+    pub score: f64
+}
+
+impl QuestionGrade {
+    pub fn to_record(&self) -> QuizAnswerRecord {
+        QuizAnswerRecord {
+            question_uuid: self.question_uuid.clone(),
+            is_correct: self.credit >= 1.0,
+            credit: self.credit,
+            weight: self.weight,
+            selected: self.selected.clone()
+        }
+    }
+}
+
+impl QuizGrade {
+    /// Результаты по вопросам для сохранения в базу
+    pub fn to_records(&self) -> Vec<QuizAnswerRecord> {
+        self.questions.iter().map(QuestionGrade::to_record).collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum QuizError {
+    /// Для сессии не выдавались вопросы
+    NotIssued {
+        session_id: i64
+    },
+    /// Ответ на вопрос, который не выдавался в этой сессии
+    QuestionNotIssued {
+        session_id: i64,
+        question_uuid: String
+    },
+    AlreadySubmitted {
+        session_id: i64
+    },
+    /// Вопросы сессии уже выданы и ждут ответов
+    AlreadyIssued {
+        session_id: i64
+    },
+    /// В банке не хватает вопросов для раздела плана теста
+    NotEnoughQuestions {
+        section: BlueprintSection,
+        available: usize
+    }
+}
+
+impl Display for QuizError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QuizError::NotIssued { session_id } =>
+                write!(f, "В сессии {} тест ещё не начат", session_id),
+            QuizError::QuestionNotIssued { session_id, question_uuid } =>
+                write!(f, "Вопрос {} не выдавался в сессии {}", question_uuid, session_id),
+            QuizError::AlreadySubmitted { session_id } =>
+                write!(f, "Ответы теста в сессии {} уже приняты", session_id),
+            QuizError::AlreadyIssued { session_id } =>
+                write!(f, "Тест в сессии {} уже выдан", session_id),
+            QuizError::NotEnoughQuestions { section, available } => write!(
+                f,
+                "Для раздела теста (категория {:?}, сложность {:?}) нужно {} вопросов, доступно {}",
+                section.category, section.difficulty, section.count, available
+            ),
+        }
+    }
+}
+
+impl std::error::Error for QuizError {}
+
+impl ToPayload for QuizError {
+    fn code_and_details(&self) -> (&'static str, Value) {
+        match self {
+            QuizError::NotIssued { session_id } =>
+                ("quiz_not_issued", json!({ "session_id": session_id })),
+            QuizError::QuestionNotIssued { session_id, question_uuid } =>
+                ("quiz_question_not_issued", json!({ "session_id": session_id, "question_uuid": question_uuid })),
+            QuizError::AlreadySubmitted { session_id } =>
+                ("quiz_already_submitted", json!({ "session_id": session_id })),
+            QuizError::AlreadyIssued { session_id } =>
+                ("quiz_already_issued", json!({ "session_id": session_id })),
+            QuizError::NotEnoughQuestions { section, available } =>
+                ("quiz_not_enough_questions", json!({ "section": section, "available": available })),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct IssuedQuiz {
+    question_uuids: Vec<String>,
+    submitted: bool
+}
+
+/// Вопросы, выданные в каждой сессии. Оценка теста возможна только по ним
+/// и только один раз.
+#[derive(Debug, Default)]
+pub struct QuizSessions {
+    sessions: HashMap<i64, IssuedQuiz>
+}
+
+impl QuizSessions {
+    /// Запоминает выданные вопросы. Тест выдаётся в сессии один раз:
+    /// иначе студент мог бы перевыдавать вопросы, пока не выпадут лёгкие.
+    pub fn issue(&mut self, session_id: i64, question_uuids: Vec<String>) -> Result<(), QuizError> {
+        match self.sessions.get(&session_id) {
+            Some(quiz) if quiz.submitted => return Err(QuizError::AlreadySubmitted { session_id }),
+            Some(_) => return Err(QuizError::AlreadyIssued { session_id }),
+            None => {}
+        }
+
+        self.sessions.insert(session_id, IssuedQuiz { question_uuids, submitted: false });
+
+        Ok(())
+    }
+
+    /// Закрывает тест сессии после сохранения результата
+    pub fn mark_submitted(&mut self, session_id: i64) -> Result<(), QuizError> {
+        let quiz = self.sessions
+            .get_mut(&session_id)
+            .ok_or(QuizError::NotIssued { session_id })?;
+
+        quiz.submitted = true;
+
+        Ok(())
+    }
+
+    /// Восстанавливает выданный тест по seed и плану из базы, если его нет
+    /// в памяти, например после перезапуска приложения. `submitted` —
+    /// ответы сессии уже сохранены. Вопросы совпадут с выданными, если пакет
+    /// схемы с тех пор не менялся.
+    pub fn restore(&mut self, session: &AssessmentSession, scheme: &CoefficientScheme, submitted: bool) -> Result<(), QuizError> {
+        let seed = match session.quiz_seed {
+            Some(seed) if !self.sessions.contains_key(&session.id) => seed,
+            _ => return Ok(())
+        };
+
+        let question_uuids = scheme
+            .generate_quiz(&session.quiz_blueprint.clone().unwrap_or_default(), seed)?
+            .iter()
+            .map(|question| question.get_uuid().clone())
+            .collect();
+        self.sessions.insert(session.id, IssuedQuiz { question_uuids, submitted });
+
+        Ok(())
+    }
+
+    /// Вопросы уже выданного теста, например для окна студента после
+    /// перезапуска приложения. Выдать другие вопросы так нельзя.
+    pub fn resume(&mut self, session: &AssessmentSession, scheme: &CoefficientScheme, submitted: bool) -> Result<Vec<QuestionView>, QuizError> {
+        let seed = session.quiz_seed.ok_or(QuizError::NotIssued { session_id: session.id })?;
+        self.restore(session, scheme, submitted)?;
+
+        if self.sessions.get(&session.id).is_some_and(|quiz| quiz.submitted) {
+            return Err(QuizError::AlreadySubmitted { session_id: session.id });
+        }
+
+        scheme.render_quiz(&session.quiz_blueprint.clone().unwrap_or_default(), seed)
+    }
+
+    pub fn get_issued(&self, session_id: i64) -> Option<&[String]> {
+        self.sessions.get(&session_id).map(|quiz| quiz.question_uuids.as_slice())
+    }
+
+    /// Оценивает ответы по схеме. Тест остаётся открытым, пока результат
+    /// не сохранён и не вызван [`QuizSessions::mark_submitted`].
+    pub fn grade(
+        &self,
+        session_id: i64,
+        scheme: &CoefficientScheme,
+        answers: &[SubmittedAnswer]
+    ) -> Result<QuizGrade, QuizError> {
+
+        let quiz = self.sessions
+            .get(&session_id)
+            .ok_or(QuizError::NotIssued { session_id })?;
+
+        if quiz.submitted {
+            return Err(QuizError::AlreadySubmitted { session_id });
+        }
+
+        if let Some(answer) = answers.iter().find(|answer| !quiz.question_uuids.contains(&answer.question_uuid)) {
+            return Err(QuizError::QuestionNotIssued {
+                session_id,
+                question_uuid: answer.question_uuid.clone()
+            });
+        }
+
+        // Вопрос, пропавший из схемы после выдачи, не оценивается
+        let questions = quiz.question_uuids
+            .iter()
+            .filter_map(|uuid| scheme.get_questions().get(uuid))
+            .map(|question| {
+                let selected = answers
+                    .iter()
+                    .find(|answer| &answer.question_uuid == question.get_uuid())
+                    .map_or(vec![], |answer| answer.answers.clone());
+
+                question.grade(selected)
+            })
+            .collect::<Vec<QuestionGrade>>();
+
+        let points = questions.iter().map(|question| question.points).sum::<f64>();
+        let max_points = questions.iter().map(|question| question.weight).sum::<f64>();
+
+        Ok(QuizGrade {
+            questions,
+            points,
+            max_points,
+            score: if max_points == 0.0 { 0.0 } else { points / max_points }
+        })
+    }
+}
+
+impl CoefficientScheme {
+    /// Собирает тест по плану. Одинаковые план и `seed` на той же схеме
+    /// дают те же вопросы в том же порядке.
+    pub fn generate_quiz(&self, blueprint: &QuizBlueprint, seed: u64) -> Result<Vec<&Question>, QuizError> {
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        // Порядок HashSet меняется от запуска к запуску, сортируем по uuid
+        let mut pool = self.get_questions().iter().collect::<Vec<&Question>>();
+        pool.sort_by(|a, b| a.uuid.cmp(&b.uuid));
+
+        let mut chosen: Vec<&Question> = vec![];
+
+        for section in &blueprint.sections {
+            let candidates = pool
+                .iter()
+                .copied()
+                .filter(|question| section.category.is_none() || question.category == section.category)
+                .filter(|question| section.difficulty.is_none() || question.difficulty == section.difficulty)
+                .filter(|question| !chosen.contains(question))
+                .collect::<Vec<&Question>>();
+
+            if candidates.len() < section.count {
+                return Err(QuizError::NotEnoughQuestions {
+                    section: section.clone(),
+                    available: candidates.len()
+                });
+            }
+
+            chosen.extend(candidates.choose_multiple(&mut rng, section.count).copied());
+        }
+
+        chosen.shuffle(&mut rng);
+
+        Ok(chosen)
+    }
+
+    /// Собирает тест по плану и готовит вопросы к выдаче студенту
+    pub fn render_quiz(&self, blueprint: &QuizBlueprint, seed: u64) -> Result<Vec<QuestionView>, QuizError> {
+        let questions = self.generate_quiz(blueprint, seed)?;
+
+        // Отдельный генератор, чтобы перемешивание вариантов не меняло выбор вопросов
+        let mut rng = StdRng::seed_from_u64(seed.wrapping_add(1));
+
+        Ok(questions
+            .into_iter()
+            .map(|question| QuestionView::render(question, blueprint.shuffle_variants.then_some(&mut rng)))
+            .collect())
+    }
+}
+
+impl Question {
+    /// Доля балла за выбранные варианты (их id) по режиму вопроса, от 0 до 1.
+    /// Неизвестные варианты считаются неправильными.
+    pub fn credit(&self, selected: &[String]) -> f64 {
+        let selected = selected.iter().collect::<HashSet<&String>>();
+
+        let correct_total = self.variants.iter().filter(|variant| variant.is_answer).count();
+        let wrong_total = self.variants.len() - correct_total;
+
+        let hits = selected
+            .iter()
+            .filter(|id| self.get_variant(id).is_some_and(|variant| variant.is_answer))
+            .count();
+        let misses = selected.len() - hits;
+
+        let full = hits == correct_total && misses == 0 && !selected.is_empty();
+
+        match self.get_grading_mode() {
+            GradingMode::SingleChoice => if full && hits == 1 { 1.0 } else { 0.0 },
+            GradingMode::ExactMatch => if full { 1.0 } else { 0.0 },
+            GradingMode::PartialCredit => {
+                if correct_total == 0 {
+                    return 0.0;
+                }
+
+                let gained = hits as f64 / correct_total as f64;
+                // Без неправильных вариантов в вопросе любой неизвестный выбор снимает всё
+                let penalty = if wrong_total == 0 {
+                    if misses > 0 { 1.0 } else { 0.0 }
+                } else {
+                    misses as f64 / wrong_total as f64
+                };
+
+                (gained - penalty).clamp(0.0, 1.0)
+            }
+        }
+    }
+
+    pub fn grade(&self, selected: Vec<String>) -> QuestionGrade {
+        let credit = self.credit(&selected);
+        let weight = self.weight.unwrap_or(DEFAULT_QUESTION_WEIGHT);
+
+        QuestionGrade {
+            question_uuid: self.uuid.clone(),
+            title: self.title.clone(),
+            grading: self.get_grading_mode(),
+            selected,
+            credit,
+            weight,
+            points: credit * weight
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{fixtures, variant_uuid, Storage};
+    use super::*;
+
+    #[test]
+    fn quiz_grades_only_issued_questions() {
+
+        let schema = fixtures::sample_scheme();
+
+        let issued = schema.get_questions().iter().take(2).collect::<Vec<&Question>>();
+        let view = QuestionView::from(issued[0]);
+        assert_eq!(view.variants.len(), issued[0].get_variants().len());
+        assert!(!serde_json::to_string(&view).unwrap().contains("is_answer"));
+
+        let correct = issued[0]
+            .get_variants()
+            .iter()
+            .filter(|variant| variant.get_answer_state())
+            .map(|variant| variant.get_id().clone())
+            .collect::<Vec<String>>();
+
+        let mut quizzes = QuizSessions::default();
+        quizzes.issue(1, issued.iter().map(|question| question.get_uuid().clone()).collect()).unwrap();
+        assert!(matches!(quizzes.issue(1, vec![]), Err(QuizError::AlreadyIssued { .. })));
+
+        let not_issued = schema.get_questions().iter().find(|question| !issued.contains(question)).unwrap();
+        let spoofed = [SubmittedAnswer { question_uuid: not_issued.get_uuid().clone(), answers: vec![] }];
+        assert!(matches!(quizzes.grade(1, &schema, &spoofed), Err(QuizError::QuestionNotIssued { .. })));
+
+        let answers = [
+            SubmittedAnswer { question_uuid: issued[0].get_uuid().clone(), answers: correct },
+            SubmittedAnswer { question_uuid: issued[1].get_uuid().clone(), answers: vec![] }
+        ];
+        let grade = quizzes.grade(1, &schema, &answers).unwrap();
+        assert_eq!(grade.questions.len(), 2);
+        assert_eq!(grade.questions[0].credit, 1.0);
+        assert_eq!(grade.questions[1].credit, 0.0);
+        assert_eq!(grade.to_records().len(), 2);
+
+        // Пока результат не сохранён, ответы можно отправить повторно
+        assert!(quizzes.grade(1, &schema, &answers).is_ok());
+        quizzes.mark_submitted(1).unwrap();
+        assert!(matches!(quizzes.grade(1, &schema, &answers), Err(QuizError::AlreadySubmitted { .. })));
+        assert!(matches!(quizzes.grade(2, &schema, &answers), Err(QuizError::NotIssued { .. })));
+    }
+
+    #[test]
+    fn grading_modes_give_expected_credit() {
+
+        let question = |grading: &str, weight: f64| json!({
+            "title": grading,
+            "grading": grading,
+            "weight": weight,
+            "variants": [
+                { "id": "a", "content": "Первый", "is_answer": true },
+                { "id": "b", "content": "Второй", "is_answer": true },
+                { "id": "c", "content": "Третий", "is_answer": false },
+                { "id": "d", "content": "Четвёртый", "is_answer": false }
+            ]
+        });
+        let schema = fixtures::scheme(json!({
+            "questions": [question("exact_match", 1.0), question("partial_credit", 2.0), question("single_choice", 1.0)]
+        }));
+        assert!(schema.validate().errors().any(|diagnostic| diagnostic.code == "single_choice_many_answers"));
+
+        let find = |title: &str| schema.get_questions().iter().find(|q| q.get_title() == title).unwrap().clone();
+        let selected = |picks: &[&str]| picks.iter().map(|pick| pick.to_string()).collect::<Vec<String>>();
+
+        let exact = find("exact_match");
+        assert_eq!(exact.credit(&selected(&["a", "b"])), 1.0);
+        assert_eq!(exact.credit(&selected(&["a"])), 0.0);
+        assert_eq!(exact.credit(&[]), 0.0);
+
+        let partial = find("partial_credit");
+        assert_eq!(partial.credit(&selected(&["a"])), 0.5);
+        assert_eq!(partial.credit(&selected(&["a", "b", "c"])), 0.5);
+        assert_eq!(partial.credit(&selected(&["a", "c", "d"])), 0.0);
+        assert_eq!(partial.grade(selected(&["a", "b"])).points, 2.0);
+    }
+
+    #[test]
+    fn quiz_follows_blueprint_and_seed() {
+
+        let questions = (0..12)
+            .map(|ind| json!({
+                "title": format!("Вопрос {}", ind),
+                "category": if ind % 2 == 0 { "Лидерство" } else { "Конфликты" },
+                "difficulty": ind % 3 + 1,
+                "variants": [{ "content": "Да", "is_answer": true }]
+            }))
+            .collect::<Vec<Value>>();
+        let json = fixtures::document(json!({ "questions": questions }));
+        let schema = CoefficientScheme::from_value(&json).unwrap();
+
+        let section = |category: &str, count| BlueprintSection { category: Some(category.into()), difficulty: None, count };
+        let blueprint = QuizBlueprint {
+            sections: vec![section("Лидерство", 3), section("Конфликты", 2)],
+            shuffle_variants: false
+        };
+
+        let quiz = schema.generate_quiz(&blueprint, 42).unwrap();
+        assert_eq!(quiz.iter().filter(|q| q.get_category().unwrap() == "Лидерство").count(), 3);
+        assert_eq!(quiz.iter().filter(|q| q.get_category().unwrap() == "Конфликты").count(), 2);
+
+        let uuids = |quiz: Vec<&Question>| quiz.iter().map(|q| q.get_uuid().clone()).collect::<Vec<String>>();
+        let reloaded = CoefficientScheme::from_value(&json).unwrap();
+        assert_eq!(uuids(quiz), uuids(reloaded.generate_quiz(&blueprint, 42).unwrap()));
+
+        let hard = QuizBlueprint {
+            sections: vec![BlueprintSection { category: Some("Лидерство".into()), difficulty: Some(3), count: 3 }],
+            shuffle_variants: false
+        };
+        assert!(matches!(
+            schema.generate_quiz(&hard, 1),
+            Err(QuizError::NotEnoughQuestions { available: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn variants_keep_authored_order_and_stable_ids() {
+
+        let schema = fixtures::sample_scheme();
+
+        let question = schema
+            .get_questions()
+            .iter()
+            .find(|q| q.get_title() == "Лидер должен обладать следующими основными чертами:")
+            .unwrap();
+        assert_eq!(question.get_variants()[2].get_content(), "быть общительным;");
+        assert_eq!(
+            question.get_variants()[2].get_id(),
+            &variant_uuid(question.get_uuid(), "быть общительным;")
+        );
+
+        let view = QuestionView::from(question);
+        assert_eq!(view.variants[0].letter, "а");
+        assert_eq!(view.variants[2].content, "быть общительным;");
+
+        let blueprint = QuizBlueprint { shuffle_variants: true, ..QuizBlueprint::default() };
+        let ids = |quiz: Vec<QuestionView>| quiz
+            .into_iter()
+            .flat_map(|q| q.variants.into_iter().map(|v| v.id))
+            .collect::<Vec<String>>();
+        let shuffled = ids(schema.render_quiz(&blueprint, 7).unwrap());
+        assert_eq!(shuffled, ids(schema.render_quiz(&blueprint, 7).unwrap()));
+
+        let authored = ids(schema.render_quiz(&QuizBlueprint::default(), 7).unwrap());
+        assert_ne!(shuffled, authored);
+    }
+
+    #[test]
+    fn reissued_quiz_repeats_questions_and_variant_order() {
+
+        let schema = fixtures::sample_scheme();
+        let mut storage = Storage::open_in_memory().unwrap();
+        let student = storage.create_student("Тест", None).unwrap();
+        let first = storage.start_session(student.id).unwrap();
+        let second = storage.start_session(student.id).unwrap();
+
+        let blueprint = QuizBlueprint { shuffle_variants: true, ..QuizBlueprint::default() };
+        let issued = schema.render_quiz(&blueprint, 42).unwrap();
+        storage.record_quiz_issue(first.id, 42, &blueprint, "Основной", "1").unwrap();
+
+        let source = storage.get_session(first.id).unwrap();
+        let (seed, blueprint) = (source.quiz_seed.unwrap(), source.quiz_blueprint.unwrap());
+        let reissued = schema.render_quiz(&blueprint, seed).unwrap();
+        storage.record_quiz_issue(second.id, seed, &blueprint, "Основной", "1").unwrap();
+
+        let order = |quiz: &[QuestionView]| quiz
+            .iter()
+            .map(|q| (q.uuid.clone(), q.variants.iter().map(|v| v.id.clone()).collect::<Vec<String>>()))
+            .collect::<Vec<(String, Vec<String>)>>();
+        assert_eq!(order(&issued), order(&reissued));
+        assert_eq!(storage.get_session(second.id).unwrap().quiz_seed, Some(42));
+    }
+
+    #[test]
+    fn issued_quiz_survives_restart() {
+
+        let schema = fixtures::sample_scheme();
+        let mut storage = Storage::open_in_memory().unwrap();
+        let student = storage.create_student("Тест", None).unwrap();
+        let session = storage.start_session(student.id).unwrap();
+
+        let blueprint = QuizBlueprint::default();
+        let issued = schema.render_quiz(&blueprint, 5).unwrap();
+        storage.record_quiz_issue(session.id, 5, &blueprint, "Основной", "1").unwrap();
+
+        // Перезапуск: в памяти ничего нет, seed и план остались в базе
+        let session = storage.get_session(session.id).unwrap();
+        let mut quizzes = QuizSessions::default();
+        let resumed = quizzes.resume(&session, &schema, false).unwrap();
+        assert_eq!(
+            resumed.iter().map(|q| &q.uuid).collect::<Vec<&String>>(),
+            issued.iter().map(|q| &q.uuid).collect::<Vec<&String>>()
+        );
+
+        let answers = [SubmittedAnswer { question_uuid: issued[0].uuid.clone(), answers: vec![] }];
+        let mut restarted = QuizSessions::default();
+        restarted.restore(&session, &schema, false).unwrap();
+        assert_eq!(restarted.grade(session.id, &schema, &answers).unwrap().questions.len(), issued.len());
+
+        let mut submitted = QuizSessions::default();
+        assert!(matches!(submitted.resume(&session, &schema, true), Err(QuizError::AlreadySubmitted { .. })));
+        assert!(matches!(submitted.grade(session.id, &schema, &answers), Err(QuizError::AlreadySubmitted { .. })));
+    }
+}
@@ -0,0 +1,351 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Display, Formatter};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{CoefficientScheme, ToPayload, Vacancy, VacancyId};
+
+pub const MIN_SKILL_LEVEL: u8 = 1;
+pub const MAX_SKILL_LEVEL: u8 = 5;
+
+/// Навыки работника в запросе фронтенда
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum WorkerSkills {
+    /// Оценка каждого качества по шкале 1–5
+    Graded(BTreeMap<String, u8>),
+    /// Старый формат: список названий, каждый навык на максимальном уровне
+    Names(Vec<String>)
+}
+
+impl WorkerSkills {
+    pub fn levels(&self) -> BTreeMap<String, u8> {
+        match self {
+            WorkerSkills::Graded(levels) => levels.clone(),
+            WorkerSkills::Names(names) => names
+                .iter()
+                .map(|name| (name.clone(), MAX_SKILL_LEVEL))
+                .collect()
+        }
+    }
+}
+
+/// Навыки работника с их весами: уровень, делённый на [`MAX_SKILL_LEVEL`]
+#[derive(Debug, Clone, Default)]
+pub struct WorkerProfile {
+    pub levels: BTreeMap<String, f64>
+}
+
+impl WorkerProfile {
+    pub fn from_skills(skills: &[String]) -> Self {
+        Self {
+            levels: skills.iter().map(|skill| (skill.clone(), 1.0)).collect()
+        }
+    }
+
+    pub fn from_levels(levels: &BTreeMap<String, u8>) -> Result<Self, ScoringError> {
+        let mut weights = BTreeMap::new();
+
+        for (skill, level) in levels {
+            if !(MIN_SKILL_LEVEL..=MAX_SKILL_LEVEL).contains(level) {
+                return Err(ScoringError::InvalidLevel {
+                    skill: skill.clone(),
+                    level: *level
+                });
+            }
+
+            weights.insert(skill.clone(), *level as f64 / MAX_SKILL_LEVEL as f64);
+        }
+
+        Ok(Self { levels: weights })
+    }
+
+    fn norm(&self) -> f64 {
+        self.levels.values().map(|level| level * level).sum::<f64>().sqrt()
+    }
+}
+
+/// Коэффициенты всех навыков, влияющих на одну вакансию
+#[derive(Debug, Clone, Default)]
+pub struct VacancyProfile {
+    pub coefficients: BTreeMap<String, i64>
+}
+
+impl VacancyProfile {
+    /// Сумма коэффициентов: балл работника, у которого все навыки на максимальном уровне
+    pub fn max_score(&self) -> f64 {
+        self.coefficients.values().sum::<i64>() as f64
+    }
+
+    fn norm(&self) -> f64 {
+        self.coefficients.values().map(|coef| (coef * coef) as f64).sum::<f64>().sqrt()
+    }
+}
+
+/// Стратегия подсчёта баллов. Вклад навыка равен `уровень * коэффициент`,
+/// стратегия задаёт, на что делится этот вклад.
+pub trait ScoringStrategy {
+    fn denominator(&self, worker: &WorkerProfile, vacancy: &VacancyProfile) -> f64;
+}
+
+/// Простая сумма коэффициентов выбранных навыков
+pub struct RawSum;
+
+/// Сумма, нормированная на балл работника со всеми навыками на максимальном уровне
+pub struct Normalized;
+
+/// Косинусное сходство вектора навыков работника и профиля вакансии
+pub struct Cosine;
+
+impl ScoringStrategy for RawSum {
+    fn denominator(&self, _worker: &WorkerProfile, _vacancy: &VacancyProfile) -> f64 {
+        1.0
+    }
+}
+
+impl ScoringStrategy for Normalized {
+    fn denominator(&self, _worker: &WorkerProfile, vacancy: &VacancyProfile) -> f64 {
+        vacancy.max_score()
+    }
+}
+
+impl ScoringStrategy for Cosine {
+    fn denominator(&self, worker: &WorkerProfile, vacancy: &VacancyProfile) -> f64 {
+        worker.norm() * vacancy.norm()
+    }
+}
+
+/// Стратегия, которую выбирает фронтенд
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScoringMethod {
+    #[default]
+    RawSum,
+    Normalized,
+    Cosine
+}
+
+impl ScoringMethod {
+    pub fn strategy(&self) -> Box<dyn ScoringStrategy> {
+        match self {
+            ScoringMethod::RawSum => Box::new(RawSum),
+            ScoringMethod::Normalized => Box::new(Normalized),
+            ScoringMethod::Cosine => Box::new(Cosine),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkillContribution {
+    pub skill: String,
+    /// Вес навыка: уровень / MAX_SKILL_LEVEL
+    pub level: f64,
+    pub coefficient: i64,
+    pub contribution: f64
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VacancyScore {
+    pub vacancy: Vacancy,
+    pub score: f64,
+    pub contributions: Vec<SkillContribution>
+}
+
+#[derive(Debug, Clone)]
+pub enum ScoringError {
+    UnknownSkill(String),
+    InvalidLevel {
+        skill: String,
+        level: u8
+    }
+}
+
+impl Display for ScoringError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScoringError::UnknownSkill(skill) => write!(f, "Навык {:?} не найден в схеме", skill),
+            ScoringError::InvalidLevel { skill, level } => write!(
+                f,
+                "Уровень {} навыка {:?} вне шкалы {}–{}",
+                level, skill, MIN_SKILL_LEVEL, MAX_SKILL_LEVEL
+            )
+        }
+    }
+}
+
+impl std::error::Error for ScoringError {}
+
+impl ToPayload for ScoringError {
+    fn code_and_details(&self) -> (&'static str, Value) {
+        match self {
+            ScoringError::UnknownSkill(skill) =>
+                ("scoring_unknown_skill", json!({ "skill": skill })),
+            ScoringError::InvalidLevel { skill, level } =>
+                ("scoring_invalid_level", json!({
+                    "skill": skill,
+                    "level": level,
+                    "min": MIN_SKILL_LEVEL,
+                    "max": MAX_SKILL_LEVEL
+                })),
+        }
+    }
+}
+
+impl CoefficientScheme {
+    pub fn get_vacancy_profiles(&self) -> HashMap<VacancyId, VacancyProfile> {
+        let mut profiles: HashMap<VacancyId, VacancyProfile> = HashMap::new();
+
+        for skill in self.get_skills() {
+            for vac_coef in skill.get_vacancies_coefficient() {
+                profiles
+                    .entry(vac_coef.get_vacancy_id())
+                    .or_default()
+                    .coefficients
+                    .insert(skill.name.clone(), vac_coef.get_coefficient());
+            }
+        }
+
+        profiles
+    }
+
+    /// Ранжирует вакансии для работника, от лучшей к худшей. Вакансии,
+    /// на которые не влияет ни один навык работника, не попадают в список.
+    pub fn score_vacancies(
+        &self,
+        worker: &WorkerProfile,
+        strategy: &dyn ScoringStrategy
+    ) -> Result<Vec<VacancyScore>, ScoringError> {
+
+        if let Some(unknown) = worker.levels.keys().find(|skill| !self.get_skills().contains(*skill)) {
+            return Err(ScoringError::UnknownSkill(unknown.clone()));
+        }
+
+        let mut scores = vec![];
+
+        for (vacancy_id, profile) in self.get_vacancy_profiles() {
+            let vacancy = match self.get_vacancy(vacancy_id) {
+                Some(vacancy) => vacancy,
+                None => continue
+            };
+
+            let denominator = strategy.denominator(worker, &profile);
+            let contributions = worker.levels
+                .iter()
+                .filter_map(|(skill, level)| {
+                    profile.coefficients.get(skill).map(|coefficient| SkillContribution {
+                        skill: skill.clone(),
+                        level: *level,
+                        coefficient: *coefficient,
+                        contribution: if denominator == 0.0 {
+                            0.0
+                        } else {
+                            level * *coefficient as f64 / denominator
+                        }
+                    })
+                })
+                .collect::<Vec<SkillContribution>>();
+
+            if contributions.is_empty() {
+                continue
+            }
+
+            scores.push(VacancyScore {
+                vacancy: vacancy.clone(),
+                score: contributions.iter().map(|c| c.contribution).sum(),
+                contributions
+            });
+        }
+
+        scores.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.vacancy.0.cmp(&b.vacancy.0))
+        });
+
+        Ok(scores)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures;
+    use super::*;
+
+    fn schema() -> CoefficientScheme {
+        fixtures::scheme(json!({
+            "vacancies": [{ "name": "Team_Lead" }, { "name": "Analytic" }],
+            "skills": {
+                "Ответственность": { "Team_Lead": 1, "Analytic": 2 },
+                "Коммуникабельность": { "Team_Lead": 1 },
+                "Внимательность": { "Team_Lead": 1 }
+            }
+        }))
+    }
+
+    fn worker() -> WorkerProfile {
+        WorkerProfile::from_skills(&["Ответственность".to_owned(), "Коммуникабельность".to_owned()])
+    }
+
+    #[test]
+    fn raw_sum_lists_contributions() {
+
+        let raw = schema().score_vacancies(&worker(), &RawSum).unwrap();
+        let team_lead = raw.iter().find(|score| score.vacancy.0 == "Team_Lead").unwrap();
+
+        assert_eq!(team_lead.score, 2.0);
+        assert_eq!(team_lead.contributions.len(), 2);
+    }
+
+    #[test]
+    fn normalized_ranks_by_share_of_maximum() {
+
+        // Analytic достигает максимума, а Team_Lead только 2 из 3
+        let normalized = schema().score_vacancies(&worker(), &Normalized).unwrap();
+
+        assert_eq!(normalized[0].vacancy.0, "Analytic");
+        assert_eq!(normalized[0].score, 1.0);
+        assert!((normalized[1].score - 2.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn cosine_score_is_bounded() {
+
+        let cosine = schema().score_vacancies(&worker(), &Cosine).unwrap();
+
+        assert!(!cosine.is_empty());
+        assert!(cosine.iter().all(|score| (0.0..=1.0 + 1e-9).contains(&score.score)));
+    }
+
+    #[test]
+    fn legacy_names_mean_max_level() {
+
+        let schema = schema();
+        let names: WorkerSkills = serde_json::from_value(json!(["Ответственность"])).unwrap();
+        let graded: WorkerSkills = serde_json::from_value(json!({ "Ответственность": 2 })).unwrap();
+
+        let full = schema.score_vacancies(&WorkerProfile::from_levels(&names.levels()).unwrap(), &RawSum).unwrap();
+        let partial = schema.score_vacancies(&WorkerProfile::from_levels(&graded.levels()).unwrap(), &RawSum).unwrap();
+
+        assert_eq!(full[0].score, 2.0);
+        assert!((partial[0].score - 0.8).abs() < 1e-9);
+    }
+
+    #[test]
+    fn out_of_scale_level_is_rejected() {
+
+        let out_of_scale = serde_json::from_value::<WorkerSkills>(json!({ "Ответственность": 7 })).unwrap();
+
+        assert!(WorkerProfile::from_levels(&out_of_scale.levels()).is_err());
+    }
+
+    #[test]
+    fn unknown_skill_is_reported() {
+
+        let unknown = WorkerProfile::from_skills(&["Храбрость".to_owned()]);
+
+        assert!(matches!(
+            schema().score_vacancies(&unknown, &RawSum),
+            Err(ScoringError::UnknownSkill(skill)) if skill == "Храбрость"
+        ));
+    }
+}
@@ -0,0 +1,236 @@
+use std::collections::{BTreeMap, HashMap};
+use serde::{Deserialize, Serialize};
+
+use crate::Company;
+
+/// Работник с баллами по вакансиям, например из `get_vacancies_for_worker`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScoredWorker {
+    pub name: String,
+    pub scores: BTreeMap<String, f64>
+}
+
+impl ScoredWorker {
+    /// Насколько работник подходит на вакансию. Неизвестная вакансия даёт 0.
+    pub fn fit(&self, vacancy: &str) -> f64 {
+        self.scores.get(vacancy).copied().unwrap_or(0.0)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SolvedPosition {
+    pub position: String,
+    pub expected_vacancy: String,
+    /// `None`, если работников меньше, чем должностей
+    pub worker: Option<String>,
+    pub fit: f64
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlacementSolution {
+    pub company: String,
+    pub assignments: Vec<SolvedPosition>,
+    /// Работники, которым не хватило должностей
+    pub unassigned_workers: Vec<String>,
+    pub total_fit: f64
+}
+
+impl Company {
+    /// Расстановка с максимальной суммарной пригодностью работников
+    /// на должностях дерева компании (венгерский алгоритм).
+    pub fn solve_placement(&self, workers: &[ScoredWorker]) -> PlacementSolution {
+
+        let positions = self.tree()
+            .get_iter()
+            .filter_map(|level| level.label().iter().next().map(|(p, v)| (p.clone(), v.clone())))
+            .collect::<Vec<(String, String)>>();
+
+        // Квадратная матрица стоимостей, фиктивные строки и столбцы нулевые
+        let size = positions.len().max(workers.len());
+        let mut cost = vec![vec![0.0; size]; size];
+        for (row, (_, vacancy)) in positions.iter().enumerate() {
+            for (col, worker) in workers.iter().enumerate() {
+                cost[row][col] = -worker.fit(vacancy);
+            }
+        }
+
+        let columns = hungarian(&cost);
+
+        let mut assigned = vec![false; workers.len()];
+        let assignments = positions
+            .into_iter()
+            .enumerate()
+            .map(|(row, (position, expected_vacancy))| {
+                let worker = workers.get(columns[row]);
+                if worker.is_some() {
+                    assigned[columns[row]] = true;
+                }
+
+                SolvedPosition {
+                    fit: worker.map_or(0.0, |worker| worker.fit(&expected_vacancy)),
+                    worker: worker.map(|worker| worker.name.clone()),
+                    position,
+                    expected_vacancy
+                }
+            })
+            .collect::<Vec<SolvedPosition>>();
+
+        let unassigned_workers = workers
+            .iter()
+            .zip(assigned)
+            .filter(|(_, assigned)| !assigned)
+            .map(|(worker, _)| worker.name.clone())
+            .collect();
+
+        PlacementSolution {
+            company: self.name().clone(),
+            total_fit: assignments.iter().map(|assignment| assignment.fit).sum(),
+            assignments,
+            unassigned_workers
+        }
+    }
+
+    /// Суммарная пригодность произвольной расстановки «должность → работник»,
+    /// чтобы сравнить ответ студента с [`Company::solve_placement`]
+    pub fn placement_fit(&self, placement: &HashMap<String, String>, workers: &[ScoredWorker]) -> f64 {
+        self.tree()
+            .get_iter()
+            .filter_map(|level| {
+                let (position, vacancy) = level.label().iter().next()?;
+                let worker_name = placement.get(position)?;
+                let worker = workers.iter().find(|worker| &worker.name == worker_name)?;
+
+                Some(worker.fit(vacancy))
+            })
+            .sum()
+    }
+}
+
+/// Венгерский алгоритм для квадратной матрицы. Возвращает номер столбца
+/// для каждой строки при минимальной суммарной стоимости.
+fn hungarian(cost: &[Vec<f64>]) -> Vec<usize> {
+
+    let n = cost.len();
+    let mut u = vec![0.0; n + 1];
+    let mut v = vec![0.0; n + 1];
+    // p[j] — строка, назначенная столбцу j (с единицы, 0 — нет)
+    let mut p = vec![0usize; n + 1];
+    let mut way = vec![0usize; n + 1];
+
+    for i in 1..=n {
+        p[0] = i;
+        let mut j0 = 0;
+        let mut minv = vec![f64::INFINITY; n + 1];
+        let mut used = vec![false; n + 1];
+
+        loop {
+            used[j0] = true;
+            let i0 = p[j0];
+            let mut delta = f64::INFINITY;
+            let mut j1 = 0;
+
+            for j in 1..=n {
+                if used[j] {
+                    continue
+                }
+
+                let cur = cost[i0 - 1][j - 1] - u[i0] - v[j];
+                if cur < minv[j] {
+                    minv[j] = cur;
+                    way[j] = j0;
+                }
+                if minv[j] < delta {
+                    delta = minv[j];
+                    j1 = j;
+                }
+            }
+
+            for j in 0..=n {
+                if used[j] {
+                    u[p[j]] += delta;
+                    v[j] -= delta;
+                } else {
+                    minv[j] -= delta;
+                }
+            }
+
+            j0 = j1;
+            if p[j0] == 0 {
+                break
+            }
+        }
+
+        loop {
+            let j1 = way[j0];
+            p[j0] = p[j1];
+            j0 = j1;
+            if j0 == 0 {
+                break
+            }
+        }
+    }
+
+    let mut columns = vec![0; n];
+    for j in 1..=n {
+        if p[j] != 0 {
+            columns[p[j] - 1] = j - 1;
+        }
+    }
+
+    columns
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use crate::fixtures;
+    use super::*;
+
+    #[test]
+    fn solver_finds_optimal_assignment() {
+
+        let schema = fixtures::scheme(json!({
+            "vacancies": [{ "name": "Team_Lead" }, { "name": "Analytic" }],
+            "companies": {
+                "Консалтинг": {
+                    "position": "Директор",
+                    "vacancy": "Team_Lead",
+                    "children": [{ "position": "Аналитик", "vacancy": "Analytic" }]
+                }
+            }
+        }));
+        let company = schema.get_companies().get(&"Консалтинг".to_owned()).unwrap();
+
+        let workers = vec![
+            ScoredWorker {
+                name: "Олег".into(),
+                scores: BTreeMap::from([("Team_Lead".into(), 3.0), ("Analytic".into(), 2.0)])
+            },
+            ScoredWorker {
+                name: "Ирина".into(),
+                scores: BTreeMap::from([("Team_Lead".into(), 2.0)])
+            },
+            ScoredWorker {
+                name: "Пётр".into(),
+                scores: BTreeMap::new()
+            },
+        ];
+
+        // Жадная расстановка дала бы 3, оптимальная — 4
+        let solution = company.solve_placement(&workers);
+        assert_eq!(solution.total_fit, 4.0);
+        assert_eq!(solution.assignments[0].worker.as_deref(), Some("Ирина"));
+        assert_eq!(solution.assignments[1].worker.as_deref(), Some("Олег"));
+        assert_eq!(solution.unassigned_workers, vec!["Пётр".to_owned()]);
+
+        let greedy = HashMap::from([
+            ("Директор".to_owned(), "Олег".to_owned()),
+            ("Аналитик".to_owned(), "Ирина".to_owned())
+        ]);
+        assert_eq!(company.placement_fit(&greedy, &workers), 3.0);
+
+        let alone = company.solve_placement(&workers[..1]);
+        assert_eq!(alone.total_fit, 3.0);
+        assert!(alone.assignments[1].worker.is_none());
+    }
+}
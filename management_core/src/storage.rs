@@ -0,0 +1,895 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{QuizBlueprint, ToPayload};
+
+/// Миграции базы результатов. Номер применённой миграции хранится в
+/// `PRAGMA user_version`, новые миграции добавляются только в конец.
+const MIGRATIONS: &[&str] = &[
+    // 1: студенты, сессии тестирования, ответы и баллы за расстановку
+    "CREATE TABLE students (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL UNIQUE,
+        -- name в нижнем регистре: lower() SQLite не понимает кириллицу
+        search_name TEXT NOT NULL,
+        created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+    );
+    CREATE TABLE sessions (
+        id INTEGER PRIMARY KEY,
+        student_id INTEGER NOT NULL REFERENCES students(id),
+        created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+    );
+    CREATE INDEX sessions_student ON sessions(student_id);
+    CREATE TABLE quiz_answers (
+        session_id INTEGER NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+        question_uuid TEXT NOT NULL,
+        is_correct INTEGER NOT NULL,
+        PRIMARY KEY (session_id, question_uuid)
+    );
+    CREATE TABLE placement_scores (
+        session_id INTEGER NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+        company TEXT,
+        score REAL NOT NULL
+    );",
+    // 2: однофамильцы — разные студенты, группа студента, завершение сессии,
+    // ранжирование вакансий из get_vacancies_for_worker
+    "CREATE TABLE students_v2 (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL,
+        search_name TEXT NOT NULL,
+        group_name TEXT,
+        created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+    );
+    INSERT INTO students_v2 (id, name, search_name, created_at)
+        SELECT id, name, search_name, created_at FROM students;
+    DROP TABLE students;
+    ALTER TABLE students_v2 RENAME TO students;
+    CREATE INDEX students_group ON students(group_name);
+    ALTER TABLE sessions ADD COLUMN finished_at TEXT;
+    CREATE TABLE vacancy_scores (
+        session_id INTEGER NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+        worker TEXT NOT NULL,
+        strategy TEXT NOT NULL,
+        vacancy TEXT NOT NULL,
+        score REAL NOT NULL
+    );",
+    // 3: частичный балл и вес вопроса
+    "ALTER TABLE quiz_answers ADD COLUMN credit REAL NOT NULL DEFAULT 0;
+    UPDATE quiz_answers SET credit = is_correct;
+    ALTER TABLE quiz_answers ADD COLUMN weight REAL NOT NULL DEFAULT 1;",
+    // 4: план и seed выданного теста, чтобы его можно было выдать повторно
+    "ALTER TABLE sessions ADD COLUMN quiz_seed INTEGER;
+    ALTER TABLE sessions ADD COLUMN quiz_blueprint TEXT;",
+    // 5: оценка способностей студента в адаптивном тесте
+    "ALTER TABLE sessions ADD COLUMN ability REAL;",
+    // 6: выбранные варианты ответа, JSON-массив id, для анализа вопросов
+    "ALTER TABLE quiz_answers ADD COLUMN selected TEXT NOT NULL DEFAULT '[]';",
+    // 7: пакет схемы и его версия, по которым посчитаны результаты сессии
+    "ALTER TABLE sessions ADD COLUMN schema_pack TEXT;
+    ALTER TABLE sessions ADD COLUMN schema_version TEXT;",
+    // 8: длина начатого адаптивного теста, чтобы продолжить его после перезапуска
+    "ALTER TABLE sessions ADD COLUMN adaptive_length INTEGER;",
+];
+
+#[derive(Debug)]
+pub enum StorageError {
+    Sqlite(rusqlite::Error),
+    Io(std::io::Error),
+    NotFound {
+        entity: &'static str,
+        id: i64
+    },
+    SessionFinished {
+        id: i64
+    },
+    /// Тест сессии, обычный или адаптивный, уже выдан: его план больше
+    /// не меняется, а второй тест не выдаётся
+    QuizIssued {
+        id: i64
+    }
+}
+
+impl From<rusqlite::Error> for StorageError {
+    fn from(value: rusqlite::Error) -> Self {
+        StorageError::Sqlite(value)
+    }
+}
+
+impl From<std::io::Error> for StorageError {
+    fn from(value: std::io::Error) -> Self {
+        StorageError::Io(value)
+    }
+}
+
+impl Display for StorageError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StorageError::Sqlite(err) => write!(f, "Ошибка базы результатов: {}", err),
+            StorageError::Io(err) => write!(f, "Ошибка доступа к базе результатов: {}", err),
+            StorageError::NotFound { entity, id } => write!(f, "{} с id {} не найден(а)", entity, id),
+            StorageError::SessionFinished { id } => write!(f, "Сессия {} уже завершена", id),
+            StorageError::QuizIssued { id } => write!(f, "Тест сессии {} уже выдан", id),
+        }
+    }
+}
+
+impl std::error::Error for StorageError {}
+
+impl ToPayload for StorageError {
+    fn code_and_details(&self) -> (&'static str, Value) {
+        match self {
+            StorageError::Sqlite(err) =>
+                ("storage", json!({ "sqlite": err.sqlite_error_code().map(|code| format!("{:?}", code)) })),
+            StorageError::Io(err) => ("storage", json!({ "kind": format!("{:?}", err.kind()) })),
+            StorageError::NotFound { entity, id } => ("not_found", json!({ "entity": entity, "id": id })),
+            StorageError::SessionFinished { id } => ("session_finished", json!({ "id": id })),
+            StorageError::QuizIssued { id } => ("session_quiz_issued", json!({ "id": id })),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Student {
+    pub id: i64,
+    pub name: String,
+    /// Учебная группа или поток
+    pub group: Option<String>,
+    pub created_at: String
+}
+
+/// Одна попытка прохождения: тест, подбор вакансий и расстановка
+#[derive(Debug, Clone, Serialize)]
+pub struct AssessmentSession {
+    pub id: i64,
+    pub student_id: i64,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    /// Пакет схемы, выбранный для сессии. `None` — пакет по умолчанию.
+    pub schema_pack: Option<String>,
+    pub schema_version: Option<String>,
+    /// План теста, заданный преподавателем. `None` — план по умолчанию.
+    pub quiz_blueprint: Option<QuizBlueprint>,
+    /// Есть, когда тест уже выдан
+    pub quiz_seed: Option<u64>,
+    /// Длина адаптивного теста. Есть, когда он начат.
+    pub adaptive_length: Option<usize>
+}
+
+impl AssessmentSession {
+    /// Выдан обычный тест или начат адаптивный. В сессии бывает только один тест.
+    pub fn is_quiz_started(&self) -> bool {
+        self.quiz_seed.is_some() || self.adaptive_length.is_some()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuizAnswerRecord {
+    pub question_uuid: String,
+    /// Полный балл за вопрос
+    pub is_correct: bool,
+    /// Доля балла от 0 до 1
+    pub credit: f64,
+    pub weight: f64,
+    /// id выбранных вариантов. Пусто у результатов, импортированных из result.json.
+    #[serde(default)]
+    pub selected: Vec<String>
+}
+
+impl QuizAnswerRecord {
+    /// Ответ, оценённый только как верный или неверный, с весом 1
+    pub fn from_result(question_uuid: String, is_correct: bool) -> Self {
+        Self {
+            question_uuid,
+            is_correct,
+            credit: if is_correct { 1.0 } else { 0.0 },
+            weight: 1.0,
+            selected: vec![]
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlacementScoreRecord {
+    pub company: Option<String>,
+    pub score: f64
+}
+
+/// Накопленные ответы на один вопрос по всем сессиям
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct QuestionStatistics {
+    pub attempts: u32,
+    /// Сумма долей балла, от 0 до attempts
+    pub credit: f64
+}
+
+/// Балл вакансии для работника, как его вернул `get_vacancies_for_worker`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VacancyScoreRecord {
+    pub worker: String,
+    pub strategy: String,
+    pub vacancy: String,
+    pub score: f64
+}
+
+/// Завершённая сессия из старого формата результатов, для импорта
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewSession {
+    pub student_name: String,
+    pub answers: Vec<QuizAnswerRecord>,
+    pub placement: Option<PlacementScoreRecord>
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SavedSession {
+    pub session_id: i64,
+    pub student_id: i64,
+    pub student_name: String,
+    pub student_group: Option<String>,
+    /// Время начала в UTC, `YYYY-MM-DD HH:MM:SS`
+    pub created_at: String,
+    pub finished_at: Option<String>,
+    pub quiz_seed: Option<u64>,
+    pub quiz_blueprint: Option<QuizBlueprint>,
+    /// Оценка способностей по адаптивному тесту
+    pub ability: Option<f64>,
+    pub schema_pack: Option<String>,
+    pub schema_version: Option<String>,
+    pub answers: Vec<QuizAnswerRecord>,
+    pub vacancy_scores: Vec<VacancyScoreRecord>,
+    pub placement: Option<PlacementScoreRecord>
+}
+
+/// Фильтр выборки сохранённых результатов. Пустые поля не ограничивают выборку.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ResultFilter {
+    pub student_id: Option<i64>,
+    /// Подстрока имени студента
+    pub student_name: Option<String>,
+    pub group: Option<String>,
+    /// Нижняя граница `created_at` включительно
+    pub from: Option<String>,
+    /// Верхняя граница `created_at` не включительно
+    pub to: Option<String>
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Page {
+    pub offset: u32,
+    pub limit: u32
+}
+
+impl Default for Page {
+    fn default() -> Self {
+        Self { offset: 0, limit: 50 }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ResultPage {
+    /// Число сессий, подходящих под фильтр, без учёта страницы
+    pub total: u32,
+    pub items: Vec<SavedSession>
+}
+
+pub struct Storage {
+    conn: Connection
+}
+
+impl Storage {
+    pub fn open(path: &Path) -> Result<Self, StorageError> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        let conn = Connection::open(path)?;
+        // WAL переживает падение приложения посреди записи
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+
+        Storage::init(conn)
+    }
+
+    pub fn open_in_memory() -> Result<Self, StorageError> {
+        Storage::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self, StorageError> {
+        let mut storage = Self { conn };
+        storage.migrate()?;
+
+        Ok(storage)
+    }
+
+    fn migrate(&mut self) -> Result<(), StorageError> {
+        let applied: usize = self.conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+
+        // Пересоздание таблиц в миграциях требует отключённых внешних ключей,
+        // а переключить их внутри транзакции нельзя
+        self.conn.pragma_update(None, "foreign_keys", false)?;
+
+        for (ind, migration) in MIGRATIONS.iter().enumerate().skip(applied) {
+            let tx = self.conn.transaction()?;
+            tx.execute_batch(migration)?;
+            tx.pragma_update(None, "user_version", ind + 1)?;
+            tx.commit()?;
+        }
+
+        self.conn.pragma_update(None, "foreign_keys", true)?;
+
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> Result<bool, StorageError> {
+        let count: i64 = self.conn.query_row("SELECT COUNT(*) FROM sessions", [], |row| row.get(0))?;
+        Ok(count == 0)
+    }
+
+    pub fn create_student(&mut self, name: &str, group: Option<&str>) -> Result<Student, StorageError> {
+        let id = insert_student(&self.conn, name, group)?;
+        self.get_student(id)
+    }
+
+    pub fn get_student(&self, id: i64) -> Result<Student, StorageError> {
+        self.conn
+            .query_row(
+                "SELECT id, name, group_name, created_at FROM students WHERE id = ?1",
+                params![id],
+                student_from_row
+            )
+            .optional()?
+            .ok_or(StorageError::NotFound { entity: "Студент", id })
+    }
+
+    /// Студенты, у которых в имени есть `query`, из группы `group`
+    pub fn find_students(&self, query: Option<&str>, group: Option<&str>) -> Result<Vec<Student>, StorageError> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, name, group_name, created_at FROM students
+             WHERE (?1 IS NULL OR instr(search_name, ?1) > 0)
+               AND (?2 IS NULL OR group_name = ?2)
+             ORDER BY name, id"
+        )?;
+
+        let students = stmt
+            .query_map(params![query.map(str::to_lowercase), group], student_from_row)?
+            .collect::<Result<Vec<Student>, rusqlite::Error>>()?;
+
+        Ok(students)
+    }
+
+    pub fn start_session(&mut self, student_id: i64) -> Result<AssessmentSession, StorageError> {
+        self.get_student(student_id)?;
+
+        self.conn.execute("INSERT INTO sessions (student_id) VALUES (?1)", params![student_id])?;
+        self.get_session(self.conn.last_insert_rowid())
+    }
+
+    pub fn get_session(&self, id: i64) -> Result<AssessmentSession, StorageError> {
+        self.conn
+            .query_row(
+                "SELECT id, student_id, created_at, finished_at, schema_pack, schema_version, quiz_blueprint, quiz_seed, adaptive_length
+                 FROM sessions WHERE id = ?1",
+                params![id],
+                |row| Ok(AssessmentSession {
+                    id: row.get(0)?,
+                    student_id: row.get(1)?,
+                    started_at: row.get(2)?,
+                    finished_at: row.get(3)?,
+                    schema_pack: row.get(4)?,
+                    schema_version: row.get(5)?,
+                    quiz_blueprint: row
+                        .get::<_, Option<String>>(6)?
+                        .and_then(|blueprint| serde_json::from_str(&blueprint).ok()),
+                    quiz_seed: row.get::<_, Option<i64>>(7)?.map(|seed| seed as u64),
+                    adaptive_length: row.get(8)?
+                })
+            )
+            .optional()?
+            .ok_or(StorageError::NotFound { entity: "Сессия", id })
+    }
+
+    /// Сессия, в которую ещё можно записывать результаты
+    pub fn get_open_session(&self, id: i64) -> Result<AssessmentSession, StorageError> {
+        let session = self.get_session(id)?;
+
+        if session.finished_at.is_some() {
+            return Err(StorageError::SessionFinished { id });
+        }
+
+        Ok(session)
+    }
+
+    pub fn finish_session(&mut self, session_id: i64) -> Result<AssessmentSession, StorageError> {
+        self.get_session(session_id)?;
+
+        self.conn.execute(
+            "UPDATE sessions SET finished_at = CURRENT_TIMESTAMP WHERE id = ?1 AND finished_at IS NULL",
+            params![session_id]
+        )?;
+
+        self.get_session(session_id)
+    }
+
+    /// Задаёт план теста сессии. Менять план можно, пока тест не выдан.
+    pub fn set_quiz_blueprint(&mut self, session_id: i64, blueprint: &QuizBlueprint) -> Result<(), StorageError> {
+        if self.get_open_session(session_id)?.is_quiz_started() {
+            return Err(StorageError::QuizIssued { id: session_id });
+        }
+
+        self.conn.execute(
+            "UPDATE sessions SET quiz_blueprint = ?2 WHERE id = ?1",
+            params![session_id, serde_json::to_string(blueprint).expect("план теста сериализуется в JSON")]
+        )?;
+
+        Ok(())
+    }
+
+    /// Запоминает, по какому плану и seed выдан тест сессии, и пакет схемы
+    /// с версией, по которым он выдан. Тест выдаётся один раз, в том числе
+    /// после перезапуска приложения, и не выдаётся, если в сессии начат
+    /// адаптивный тест или уже есть ответы.
+    pub fn record_quiz_issue(
+        &mut self,
+        session_id: i64,
+        seed: u64,
+        blueprint: &QuizBlueprint,
+        pack: &str,
+        version: &str
+    ) -> Result<(), StorageError> {
+
+        if self.get_open_session(session_id)?.is_quiz_started() || self.has_quiz_answers(session_id)? {
+            return Err(StorageError::QuizIssued { id: session_id });
+        }
+
+        self.conn.execute(
+            "UPDATE sessions SET quiz_seed = ?2, quiz_blueprint = ?3, schema_pack = ?4, schema_version = ?5 WHERE id = ?1",
+            params![
+                session_id,
+                seed as i64,
+                serde_json::to_string(blueprint).expect("план теста сериализуется в JSON"),
+                pack,
+                version
+            ]
+        )?;
+
+        Ok(())
+    }
+
+    /// Запоминает начало адаптивного теста, пакет схемы и его версию. Тест
+    /// начинается один раз и не начинается, если в сессии уже выдан обычный
+    /// тест или есть ответы.
+    pub fn record_adaptive_start(&mut self, session_id: i64, length: usize, pack: &str, version: &str) -> Result<(), StorageError> {
+        if self.get_open_session(session_id)?.is_quiz_started() || self.has_quiz_answers(session_id)? {
+            return Err(StorageError::QuizIssued { id: session_id });
+        }
+
+        self.conn.execute(
+            "UPDATE sessions SET adaptive_length = ?2, schema_pack = ?3, schema_version = ?4 WHERE id = ?1",
+            params![session_id, length, pack, version]
+        )?;
+
+        Ok(())
+    }
+
+    /// Выбирает пакет схемы сессии. Версия запишется с первым результатом.
+    /// После выдачи теста пакет не меняется: тест оценивается по тому
+    /// пакету, по которому выдан.
+    pub fn select_schema_pack(&mut self, session_id: i64, pack: &str) -> Result<(), StorageError> {
+        if self.get_open_session(session_id)?.is_quiz_started() {
+            return Err(StorageError::QuizIssued { id: session_id });
+        }
+
+        self.conn.execute(
+            "UPDATE sessions SET schema_pack = ?2, schema_version = NULL WHERE id = ?1",
+            params![session_id, pack]
+        )?;
+
+        Ok(())
+    }
+
+    /// Запоминает пакет схемы и версию, по которым посчитан первый результат
+    /// сессии. Уже записанные пакет и версия не меняются.
+    pub fn record_schema_pack(&mut self, session_id: i64, pack: &str, version: &str) -> Result<(), StorageError> {
+        self.get_open_session(session_id)?;
+
+        self.conn.execute(
+            "UPDATE sessions SET schema_pack = ?2, schema_version = ?3 WHERE id = ?1 AND schema_version IS NULL",
+            params![session_id, pack, version]
+        )?;
+
+        Ok(())
+    }
+
+    pub fn record_ability(&mut self, session_id: i64, ability: f64) -> Result<(), StorageError> {
+        self.get_open_session(session_id)?;
+
+        self.conn.execute("UPDATE sessions SET ability = ?2 WHERE id = ?1", params![session_id, ability])?;
+
+        Ok(())
+    }
+
+    /// Ответы на вопросы теста. Повторный ответ на вопрос заменяет прежний.
+    pub fn record_quiz_answers(&mut self, session_id: i64, answers: &[QuizAnswerRecord]) -> Result<(), StorageError> {
+        self.get_open_session(session_id)?;
+
+        let tx = self.conn.transaction()?;
+        insert_quiz_answers(&tx, session_id, answers)?;
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    /// Ранжирование вакансий для работника. Заменяет прежнее ранжирование
+    /// того же работника в этой сессии.
+    pub fn record_vacancy_scores(&mut self, session_id: i64, scores: &[VacancyScoreRecord]) -> Result<(), StorageError> {
+        self.get_open_session(session_id)?;
+
+        let tx = self.conn.transaction()?;
+        for worker in scores.iter().map(|score| &score.worker) {
+            tx.execute(
+                "DELETE FROM vacancy_scores WHERE session_id = ?1 AND worker = ?2",
+                params![session_id, worker]
+            )?;
+        }
+        for score in scores {
+            tx.execute(
+                "INSERT INTO vacancy_scores (session_id, worker, strategy, vacancy, score) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![session_id, score.worker, score.strategy, score.vacancy, score.score]
+            )?;
+        }
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    /// Балл за расстановку. В отчётах учитывается последняя проверка.
+    pub fn record_placement(&mut self, session_id: i64, placement: &PlacementScoreRecord) -> Result<(), StorageError> {
+        self.get_open_session(session_id)?;
+
+        self.conn.execute(
+            "INSERT INTO placement_scores (session_id, company, score) VALUES (?1, ?2, ?3)",
+            params![session_id, placement.company, placement.score]
+        )?;
+
+        Ok(())
+    }
+
+    /// Импортирует завершённую сессию старого формата в одной транзакции.
+    /// Студент ищется по точному совпадению имени.
+    pub fn import_session(&mut self, session: &NewSession) -> Result<i64, StorageError> {
+        let tx = self.conn.transaction()?;
+
+        let student_id: i64 = match tx
+            .query_row(
+                "SELECT id FROM students WHERE name = ?1 ORDER BY id LIMIT 1",
+                params![session.student_name],
+                |row| row.get(0)
+            )
+            .optional()? {
+            Some(id) => id,
+            None => insert_student(&tx, &session.student_name, None)?
+        };
+
+        tx.execute(
+            "INSERT INTO sessions (student_id, finished_at) VALUES (?1, CURRENT_TIMESTAMP)",
+            params![student_id]
+        )?;
+        let session_id = tx.last_insert_rowid();
+
+        insert_quiz_answers(&tx, session_id, &session.answers)?;
+
+        if let Some(placement) = &session.placement {
+            tx.execute(
+                "INSERT INTO placement_scores (session_id, company, score) VALUES (?1, ?2, ?3)",
+                params![session_id, placement.company, placement.score]
+            )?;
+        }
+
+        tx.commit()?;
+
+        Ok(session_id)
+    }
+
+    pub fn has_quiz_answers(&self, session_id: i64) -> Result<bool, StorageError> {
+        let exists = self.conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM quiz_answers WHERE session_id = ?1)",
+            params![session_id],
+            |row| row.get(0)
+        )?;
+
+        Ok(exists)
+    }
+
+    /// Ответы сессии в порядке записи
+    pub fn session_quiz_answers(&self, session_id: i64) -> Result<Vec<QuizAnswerRecord>, StorageError> {
+        let mut stmt = self.conn.prepare(
+            "SELECT question_uuid, is_correct, credit, weight, selected FROM quiz_answers WHERE session_id = ?1 ORDER BY rowid"
+        )?;
+
+        let answers = stmt
+            .query_map(params![session_id], quiz_answer_from_row)?
+            .collect::<Result<Vec<QuizAnswerRecord>, rusqlite::Error>>()?;
+
+        Ok(answers)
+    }
+
+    /// Все сохранённые ответы на вопросы с id их сессий
+    pub fn all_quiz_answers(&self) -> Result<Vec<(i64, QuizAnswerRecord)>, StorageError> {
+        let mut stmt = self.conn.prepare(
+            "SELECT question_uuid, is_correct, credit, weight, selected, session_id FROM quiz_answers ORDER BY session_id, rowid"
+        )?;
+
+        let answers = stmt
+            .query_map([], |row| Ok((row.get(5)?, quiz_answer_from_row(row)?)))?
+            .collect::<Result<Vec<(i64, QuizAnswerRecord)>, rusqlite::Error>>()?;
+
+        Ok(answers)
+    }
+
+    /// Статистика ответов по каждому вопросу, который хоть раз оценивался
+    pub fn question_statistics(&self) -> Result<HashMap<String, QuestionStatistics>, StorageError> {
+        let mut stmt = self.conn.prepare(
+            "SELECT question_uuid, COUNT(*), SUM(credit) FROM quiz_answers GROUP BY question_uuid"
+        )?;
+
+        let statistics = stmt
+            .query_map([], |row| Ok((
+                row.get::<_, String>(0)?,
+                QuestionStatistics { attempts: row.get(1)?, credit: row.get(2)? }
+            )))?
+            .collect::<Result<HashMap<String, QuestionStatistics>, rusqlite::Error>>()?;
+
+        Ok(statistics)
+    }
+
+    pub fn query_sessions(&self, filter: &ResultFilter, page: &Page) -> Result<ResultPage, StorageError> {
+
+        const WHERE: &str = "
+            FROM sessions s JOIN students st ON st.id = s.student_id
+            WHERE (?1 IS NULL OR instr(st.search_name, ?1) > 0)
+              AND (?2 IS NULL OR s.created_at >= ?2)
+              AND (?3 IS NULL OR s.created_at < ?3)
+              AND (?4 IS NULL OR st.id = ?4)
+              AND (?5 IS NULL OR st.group_name = ?5)";
+
+        let student_name = filter.student_name.as_ref().map(|name| name.to_lowercase());
+
+        let total: u32 = self.conn.query_row(
+            &format!("SELECT COUNT(*) {}", WHERE),
+            params![student_name, filter.from, filter.to, filter.student_id, filter.group],
+            |row| row.get(0)
+        )?;
+
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT s.id, st.id, st.name, st.group_name, s.created_at, s.finished_at, s.quiz_seed, s.quiz_blueprint, s.ability, s.schema_pack, s.schema_version {}
+             ORDER BY s.created_at DESC, s.id DESC LIMIT ?6 OFFSET ?7",
+            WHERE
+        ))?;
+
+        let mut items = stmt
+            .query_map(
+                params![
+                    student_name, filter.from, filter.to, filter.student_id, filter.group,
+                    page.limit, page.offset
+                ],
+                |row| Ok(SavedSession {
+                    session_id: row.get(0)?,
+                    student_id: row.get(1)?,
+                    student_name: row.get(2)?,
+                    student_group: row.get(3)?,
+                    created_at: row.get(4)?,
+                    finished_at: row.get(5)?,
+                    quiz_seed: row.get::<_, Option<i64>>(6)?.map(|seed| seed as u64),
+                    quiz_blueprint: row
+                        .get::<_, Option<String>>(7)?
+                        .and_then(|blueprint| serde_json::from_str(&blueprint).ok()),
+                    ability: row.get(8)?,
+                    schema_pack: row.get(9)?,
+                    schema_version: row.get(10)?,
+                    answers: vec![],
+                    vacancy_scores: vec![],
+                    placement: None
+                })
+            )?
+            .collect::<Result<Vec<SavedSession>, rusqlite::Error>>()?;
+
+        let mut answers = self.conn.prepare(
+            "SELECT question_uuid, is_correct, credit, weight, selected FROM quiz_answers WHERE session_id = ?1 ORDER BY rowid"
+        )?;
+        let mut vacancy_scores = self.conn.prepare(
+            "SELECT worker, strategy, vacancy, score FROM vacancy_scores WHERE session_id = ?1 ORDER BY rowid"
+        )?;
+        let mut placements = self.conn.prepare(
+            "SELECT company, score FROM placement_scores WHERE session_id = ?1 ORDER BY rowid DESC LIMIT 1"
+        )?;
+
+        for item in &mut items {
+            item.answers = answers
+                .query_map(params![item.session_id], quiz_answer_from_row)?
+                .collect::<Result<Vec<QuizAnswerRecord>, rusqlite::Error>>()?;
+
+            item.vacancy_scores = vacancy_scores
+                .query_map(params![item.session_id], |row| Ok(VacancyScoreRecord {
+                    worker: row.get(0)?,
+                    strategy: row.get(1)?,
+                    vacancy: row.get(2)?,
+                    score: row.get(3)?
+                }))?
+                .collect::<Result<Vec<VacancyScoreRecord>, rusqlite::Error>>()?;
+
+            item.placement = placements
+                .query_row(params![item.session_id], |row| Ok(PlacementScoreRecord {
+                    company: row.get(0)?,
+                    score: row.get(1)?
+                }))
+                .optional()?;
+        }
+
+        Ok(ResultPage { total, items })
+    }
+}
+
+fn student_from_row(row: &rusqlite::Row) -> rusqlite::Result<Student> {
+    Ok(Student {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        group: row.get(2)?,
+        created_at: row.get(3)?
+    })
+}
+
+fn insert_student(conn: &Connection, name: &str, group: Option<&str>) -> Result<i64, StorageError> {
+    conn.execute(
+        "INSERT INTO students (name, search_name, group_name) VALUES (?1, ?2, ?3)",
+        params![name, name.to_lowercase(), group]
+    )?;
+
+    Ok(conn.last_insert_rowid())
+}
+
+fn quiz_answer_from_row(row: &rusqlite::Row) -> rusqlite::Result<QuizAnswerRecord> {
+    Ok(QuizAnswerRecord {
+        question_uuid: row.get(0)?,
+        is_correct: row.get(1)?,
+        credit: row.get(2)?,
+        weight: row.get(3)?,
+        selected: serde_json::from_str(&row.get::<_, String>(4)?).unwrap_or_default()
+    })
+}
+
+fn insert_quiz_answers(conn: &Connection, session_id: i64, answers: &[QuizAnswerRecord]) -> Result<(), StorageError> {
+    for answer in answers {
+        conn.execute(
+            "INSERT OR REPLACE INTO quiz_answers (session_id, question_uuid, is_correct, credit, weight, selected)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                session_id,
+                answer.question_uuid,
+                answer.is_correct,
+                answer.credit,
+                answer.weight,
+                serde_json::to_string(&answer.selected).expect("список id сериализуется в JSON")
+            ]
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn storage_saves_and_pages_sessions() {
+
+        let mut storage = Storage::open_in_memory().unwrap();
+        assert!(storage.is_empty().unwrap());
+
+        for name in ["Иванов", "Петрова", "Иванов"] {
+            storage.import_session(&NewSession {
+                student_name: name.into(),
+                answers: vec![
+                    QuizAnswerRecord::from_result("q1".into(), true),
+                    QuizAnswerRecord::from_result("q2".into(), false),
+                ],
+                placement: Some(PlacementScoreRecord { company: None, score: 0.2 })
+            }).unwrap();
+        }
+
+        let filter = ResultFilter { student_name: Some("иван".into()), ..ResultFilter::default() };
+        let page = storage.query_sessions(&filter, &Page { offset: 0, limit: 1 }).unwrap();
+        assert_eq!(page.total, 2);
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.items[0].answers.len(), 2);
+        assert_eq!(page.items[0].placement.as_ref().unwrap().score, 0.2);
+
+        let all = storage.query_sessions(&ResultFilter::default(), &Page::default()).unwrap();
+        assert_eq!(all.total, 3);
+        assert_eq!(all.items[0].student_id, all.items[2].student_id);
+    }
+
+    #[test]
+    fn sessions_link_results_to_student() {
+
+        let mut storage = Storage::open_in_memory().unwrap();
+
+        let first = storage.create_student("Иванов", Some("ПИ-21")).unwrap();
+        let second = storage.create_student("Иванов", Some("ПИ-22")).unwrap();
+        assert_ne!(first.id, second.id);
+        assert_eq!(storage.find_students(Some("иван"), Some("ПИ-22")).unwrap().len(), 1);
+
+        let session = storage.start_session(first.id).unwrap();
+        let blueprint = QuizBlueprint::default();
+        storage.set_quiz_blueprint(session.id, &blueprint).unwrap();
+        storage.record_quiz_issue(session.id, 7, &blueprint, "Основной", "1").unwrap();
+        assert_eq!(storage.get_session(session.id).unwrap().quiz_seed, Some(7));
+        assert!(matches!(storage.record_quiz_issue(session.id, 8, &blueprint, "Основной", "1"), Err(StorageError::QuizIssued { .. })));
+        assert!(matches!(storage.set_quiz_blueprint(session.id, &blueprint), Err(StorageError::QuizIssued { .. })));
+        assert!(matches!(storage.record_adaptive_start(session.id, 5, "Основной", "1"), Err(StorageError::QuizIssued { .. })));
+
+        assert!(!storage.has_quiz_answers(session.id).unwrap());
+        storage.record_quiz_answers(session.id, &[
+            QuizAnswerRecord::from_result("q1".into(), true)
+        ]).unwrap();
+        assert!(storage.has_quiz_answers(session.id).unwrap());
+        storage.record_vacancy_scores(session.id, &[VacancyScoreRecord {
+            worker: "Олег".into(),
+            strategy: "raw_sum".into(),
+            vacancy: "Team_Lead".into(),
+            score: 4.0
+        }]).unwrap();
+        storage.record_placement(session.id, &PlacementScoreRecord { company: Some("Консалтинг".into()), score: 0.1 }).unwrap();
+        storage.record_placement(session.id, &PlacementScoreRecord { company: Some("Консалтинг".into()), score: 0.2 }).unwrap();
+        assert!(storage.finish_session(session.id).unwrap().finished_at.is_some());
+
+        let filter = ResultFilter { student_id: Some(first.id), ..ResultFilter::default() };
+        let page = storage.query_sessions(&filter, &Page::default()).unwrap();
+        assert_eq!(page.total, 1);
+        assert_eq!(page.items[0].student_group.as_deref(), Some("ПИ-21"));
+        assert_eq!(page.items[0].vacancy_scores.len(), 1);
+        assert_eq!(page.items[0].placement.as_ref().unwrap().score, 0.2);
+
+        assert!(matches!(storage.start_session(999), Err(StorageError::NotFound { .. })));
+    }
+
+    #[test]
+    fn adaptive_start_blocks_fixed_quiz() {
+
+        let mut storage = Storage::open_in_memory().unwrap();
+        let student = storage.create_student("Иванов", None).unwrap();
+        let session = storage.start_session(student.id).unwrap();
+        let blueprint = QuizBlueprint::default();
+
+        storage.record_adaptive_start(session.id, 5, "Основной", "1").unwrap();
+        assert_eq!(storage.get_session(session.id).unwrap().adaptive_length, Some(5));
+        assert!(matches!(storage.record_adaptive_start(session.id, 5, "Основной", "1"), Err(StorageError::QuizIssued { .. })));
+        assert!(matches!(storage.record_quiz_issue(session.id, 7, &blueprint, "Основной", "1"), Err(StorageError::QuizIssued { .. })));
+        assert!(matches!(storage.set_quiz_blueprint(session.id, &blueprint), Err(StorageError::QuizIssued { .. })));
+    }
+
+    #[test]
+    fn schema_pack_is_fixed_at_quiz_issue() {
+
+        let mut storage = Storage::open_in_memory().unwrap();
+        let student = storage.create_student("Иванов", None).unwrap();
+        let session = storage.start_session(student.id).unwrap();
+
+        storage.select_schema_pack(session.id, "Новый").unwrap();
+        storage.record_schema_pack(session.id, "Новый", "1").unwrap();
+        storage.record_schema_pack(session.id, "Новый", "2").unwrap();
+        assert_eq!(storage.get_session(session.id).unwrap().schema_version.as_deref(), Some("1"));
+
+        storage.record_quiz_issue(session.id, 7, &QuizBlueprint::default(), "Новый", "3").unwrap();
+        let issued = storage.get_session(session.id).unwrap();
+        assert_eq!((issued.schema_pack.as_deref(), issued.schema_version.as_deref()), (Some("Новый"), Some("3")));
+
+        assert!(matches!(storage.select_schema_pack(session.id, "Основной"), Err(StorageError::QuizIssued { .. })));
+        storage.record_schema_pack(session.id, "Новый", "4").unwrap();
+        assert_eq!(storage.get_session(session.id).unwrap().schema_version.as_deref(), Some("3"));
+    }
+}
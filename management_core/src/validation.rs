@@ -0,0 +1,90 @@
+use std::fmt::{Display, Formatter};
+use std::ops::RangeInclusive;
+use serde::Serialize;
+
+/// Допустимые значения коэффициента навык → вакансия
+pub const COEFFICIENT_RANGE: RangeInclusive<i64> = 0..=5;
+
+/// Допустимые значения сложности вопроса
+pub const DIFFICULTY_RANGE: RangeInclusive<i64> = 1..=5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Warning,
+    Error
+}
+
+/// Одна проблема схемы. `pointer` указывает на место в skill_coefficients.json
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: &'static str,
+    pub pointer: String,
+    pub message: String
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "предупреждение",
+            Severity::Error => "ошибка"
+        };
+
+        write!(f, "[{}] {} ({}): {}", severity, self.pointer, self.code, self.message)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ValidationReport {
+    pub diagnostics: Vec<Diagnostic>
+}
+
+impl ValidationReport {
+    pub fn error(&mut self, code: &'static str, pointer: impl Into<String>, message: impl Into<String>) {
+        self.diagnostics.push(Diagnostic {
+            severity: Severity::Error,
+            code,
+            pointer: pointer.into(),
+            message: message.into()
+        });
+    }
+
+    pub fn warning(&mut self, code: &'static str, pointer: impl Into<String>, message: impl Into<String>) {
+        self.diagnostics.push(Diagnostic {
+            severity: Severity::Warning,
+            code,
+            pointer: pointer.into(),
+            message: message.into()
+        });
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics.iter().filter(|d| d.severity == Severity::Error)
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics.iter().filter(|d| d.severity == Severity::Warning)
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.errors().next().is_some()
+    }
+}
+
+impl Display for ValidationReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Ошибок: {}, предупреждений: {}",
+            self.errors().count(),
+            self.warnings().count()
+        )?;
+
+        for diagnostic in &self.diagnostics {
+            writeln!(f, "{}", diagnostic)?;
+        }
+
+        Ok(())
+    }
+}
@@ -3,25 +3,41 @@
     windows_subsystem = "windows"
 )]
 
-use rand::seq::SliceRandom;
-use std::cell::RefCell;
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
-use std::f64::NAN;
+use std::fmt::{Display, Formatter};
 use std::fs;
 use std::fs::File;
-use std::io::{Read, Seek, SeekFrom, Write};
-use std::path::Path;
-use std::sync::Mutex;
-use rand::prelude::IteratorRandom;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Mutex, MutexGuard, PoisonError};
+use std::thread;
+use std::time::Duration;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use rand::Rng;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
-use tauri::State;
-use tauri::WindowUrl::App;
-use management_core::{SchemaError, CoefficientScheme,  Skill, Vacancy, VacancyCoefficient, Job, Company, JobLevel, Question, AnswerVariant};
+use serde_json::{json, Value};
+use tauri::{AppHandle, Manager, PathResolver, State};
+use tauri::api::dialog::blocking::FileDialogBuilder;
+use tauri::api::dialog::{MessageDialogBuilder, MessageDialogKind};
+use management_core::{serialize_as_payload, SchemaError, ErrorPayload, ToPayload, ScoringError, CoefficientScheme, SkillView, Vacancy, Company, ValidationReport, LATEST_SCHEMA_VERSION, upgrade_schema_file, ScoringMethod, VacancyScore, WorkerProfile, WorkerSkills, PlacedWorker, PlacementConfig, PlacementError, PlacementReport, PlacementSolution, ScoredWorker, Storage, StorageError, NewSession, QuizAnswerRecord, PlacementScoreRecord, VacancyScoreRecord, ResultFilter, Page, ResultPage, Student, AssessmentSession, QuestionView, SubmittedAnswer, QuizGrade, QuizError, QuizSessions, QuizBlueprint, AdaptiveSessions, AdaptiveStep, DEFAULT_ADAPTIVE_LENGTH, ItemAnalysisReport, AuthoringError, PositionDocument, QuestionDocumentV2, SchemaDocument, SchemaPack, SchemaPacks, PackInfo, PACK_EXTENSION, ArchiveError, PackArchive, PackManifest, ARCHIVE_EXTENSION, MatrixImport, MatrixError, Gradebook, GradebookError};
 
 #[derive(Debug)]
 pub enum AppError {
     SchemaError(SchemaError),
+    Scoring(ScoringError),
+    Placement(PlacementError),
+    Storage(StorageError),
+    Quiz(QuizError),
+    Authoring(AuthoringError),
+    Archive(ArchiveError),
+    Matrix(MatrixError),
+    Gradebook(GradebookError),
+    Io(io::Error),
+    Json(serde_json::Error),
+    NotFound {
+        entity: &'static str,
+        name: String
+    },
     Custom {
         name: String,
         description: String
@@ -34,260 +50,1193 @@ impl From<SchemaError> for AppError {
     }
 }
 
-#[derive(Serialize)]
+impl From<ScoringError> for AppError {
+    fn from(value: ScoringError) -> Self {
+        AppError::Scoring(value)
+    }
+}
+
+impl From<PlacementError> for AppError {
+    fn from(value: PlacementError) -> Self {
+        AppError::Placement(value)
+    }
+}
+
+impl From<StorageError> for AppError {
+    fn from(value: StorageError) -> Self {
+        AppError::Storage(value)
+    }
+}
+
+impl From<QuizError> for AppError {
+    fn from(value: QuizError) -> Self {
+        AppError::Quiz(value)
+    }
+}
+
+impl From<AuthoringError> for AppError {
+    fn from(value: AuthoringError) -> Self {
+        AppError::Authoring(value)
+    }
+}
+
+impl From<ArchiveError> for AppError {
+    fn from(value: ArchiveError) -> Self {
+        AppError::Archive(value)
+    }
+}
+
+impl From<MatrixError> for AppError {
+    fn from(value: MatrixError) -> Self {
+        AppError::Matrix(value)
+    }
+}
+
+impl From<GradebookError> for AppError {
+    fn from(value: GradebookError) -> Self {
+        AppError::Gradebook(value)
+    }
+}
+
+impl From<io::Error> for AppError {
+    fn from(value: io::Error) -> Self {
+        AppError::Io(value)
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(value: serde_json::Error) -> Self {
+        AppError::Json(value)
+    }
+}
+
+impl Display for AppError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AppError::SchemaError(err) => write!(f, "{}", err),
+            AppError::Scoring(err) => write!(f, "{}", err),
+            AppError::Placement(err) => write!(f, "{}", err),
+            AppError::Storage(err) => write!(f, "{}", err),
+            AppError::Quiz(err) => write!(f, "{}", err),
+            AppError::Authoring(err) => write!(f, "{}", err),
+            AppError::Archive(err) => write!(f, "{}", err),
+            AppError::Matrix(err) => write!(f, "{}", err),
+            AppError::Gradebook(err) => write!(f, "{}", err),
+            AppError::Io(err) => write!(f, "Ошибка ввода-вывода: {}", err),
+            AppError::Json(err) => write!(f, "Ошибка JSON: {}", err),
+            AppError::NotFound { entity, name } => write!(f, "{} {:?} не найден(а)", entity, name),
+            AppError::Custom { name, description } => write!(f, "{}: {}", name, description)
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl ToPayload for AppError {
+    fn code_and_details(&self) -> (&'static str, Value) {
+        match self {
+            AppError::SchemaError(err) => err.code_and_details(),
+            AppError::Scoring(err) => err.code_and_details(),
+            AppError::Placement(err) => err.code_and_details(),
+            AppError::Storage(err) => err.code_and_details(),
+            AppError::Quiz(err) => err.code_and_details(),
+            AppError::Authoring(err) => err.code_and_details(),
+            AppError::Archive(err) => err.code_and_details(),
+            AppError::Matrix(err) => err.code_and_details(),
+            AppError::Gradebook(err) => err.code_and_details(),
+            AppError::Io(err) => ("io", json!({ "kind": format!("{:?}", err.kind()) })),
+            AppError::Json(err) => ("json", json!({ "line": err.line(), "column": err.column() })),
+            AppError::NotFound { entity, name } => ("not_found", json!({ "entity": entity, "name": name })),
+            AppError::Custom { name, .. } => ("custom", json!({ "name": name })),
+        }
+    }
+}
+
+// Ошибки уходят во фронтенд как `{code, message, details}`
+serialize_as_payload!(AppError);
+
+/// Блокирует общее состояние. Команды не паникуют под блокировкой, но если
+/// мьютекс всё же отравлен, данные в нём целы и работу можно продолжать.
+fn lock<T>(state: &Mutex<T>) -> MutexGuard<'_, T> {
+    state.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
 pub struct ManagementApp {
-    schema: CoefficientScheme
+    packs: SchemaPacks,
+    /// Каталог, в который устанавливаются импортированные пакеты
+    packs_dir: PathBuf
 }
 
+/// Файл схемы, пакет по умолчанию. Лежит в каталоге данных приложения
+/// рядом с базой результатов, при первом запуске копируется из ресурсов.
+const SCHEMA_FILE: &str = "skill_coefficients.json";
+
+/// Каталог пакетов схем для разных вариантов курса в каталоге данных приложения
+const PACKS_DIR: &str = "schemas";
+
+/// Пакет перезагружен, в событии сведения о пакете и отчёт валидации
+const SCHEMA_RELOADED_EVENT: &str = "schema-reloaded";
+
+/// Изменённый файл пакета не прошёл проверку, работает прежняя версия
+const SCHEMA_RELOAD_FAILED_EVENT: &str = "schema-reload-failed";
+
+/// Редакторы сохраняют файл в несколько приёмов: перезагружаем схему,
+/// когда события от файла стихнут на это время
+const RELOAD_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Форматы таблиц: матрица коэффициентов, ведомость
+const XLSX_EXTENSION: &str = "xlsx";
+const CSV_EXTENSION: &str = "csv";
+
 impl ManagementApp {
-    pub fn new(config: &Path) -> Result<Self, AppError> {
+    /// Сначала загружается `config`, он становится пакетом по умолчанию,
+    /// затем пакеты из `packs_dir`. Пакеты каталога с ошибками пропускаются.
+    pub fn new(config: &Path, packs_dir: &Path) -> Result<Self, AppError> {
 
-        let config_f = File::open(config).unwrap();
+        let mut packs = SchemaPacks::default();
 
-        Ok(Self {
-            schema: CoefficientScheme::new(config_f)?
-        })
+        let load_config = config.exists() || !packs_dir.is_dir();
+        if load_config {
+            packs.insert(SchemaPack::load(config)?);
+        }
+
+        if packs_dir.is_dir() {
+            for (path, err) in packs.load_dir(packs_dir)? {
+                println!("Пакет {:?} не загружен: {}\n------------", path, err);
+            }
+        }
+
+        if packs.is_empty() {
+            return Err(AppError::NotFound { entity: "Пакет схемы", name: packs_dir.display().to_string() });
+        }
+
+        // Пакет каталога с тем же именем заменяет config
+        if let Some(pack) = packs.get_default().filter(|pack| load_config && pack.get_path() != config) {
+            println!("Пакет {} из {:?} заменён пакетом из {:?}\n------------", pack.get_name(), config, pack.get_path());
+        }
+
+        for pack in packs.iter() {
+            for warning in pack.get_schema().validate().warnings() {
+                println!("{}: {}\n------------", pack.get_name(), warning);
+            }
+        }
+
+        Ok(Self { packs, packs_dir: packs_dir.to_path_buf() })
+    }
+
+    /// Пакет по имени, без имени — пакет по умолчанию
+    fn pack(&self, name: Option<&str>) -> Result<&SchemaPack, AppError> {
+        match name {
+            Some(name) => self.packs.get(name),
+            None => self.packs.get_default()
+        }.ok_or_else(|| AppError::NotFound { entity: "Пакет схемы", name: name.unwrap_or_default().to_owned() })
+    }
+}
+
+/// Пакет, по которому считаются результаты сессии: выбранный для неё или
+/// пакет по умолчанию. Имя и версия пакета записываются в сессию с первым
+/// результатом и при выдаче теста.
+fn session_pack<'a>(app: &'a ManagementApp, storage: &mut Storage, session_id: i64) -> Result<&'a SchemaPack, AppError> {
+
+    let session = storage.get_open_session(session_id)?;
+    let pack = app.pack(session.schema_pack.as_deref())?;
+    storage.record_schema_pack(session_id, pack.get_name(), pack.get_version())?;
+
+    return Ok(pack);
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SchemaReloaded {
+    pack: PackInfo,
+    report: ValidationReport
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SchemaReloadFailed {
+    path: PathBuf,
+    error: ErrorPayload
+}
+
+/// Перечитывает файл пакета и подменяет пакет, только если схема прошла
+/// проверку. Новый файл в каталоге пакетов добавляет пакет. Окна узнают
+/// о результате из события.
+fn reload_pack(app: &AppHandle, path: &Path) {
+
+    // Разбираем файл без блокировки, чтобы не задерживать команды
+    let emitted = match SchemaPack::load(path) {
+        Ok(pack) => {
+            let report = pack.get_schema().validate();
+
+            let state = app.state::<Mutex<ManagementApp>>();
+            let mut management_app = lock(&state);
+            let info = management_app.packs.info(&pack);
+            management_app.packs.insert(pack);
+            drop(management_app);
+
+            println!("Пакет {} перезагружен, версия {}\n------------", info.name, info.version);
+            app.emit_all(SCHEMA_RELOADED_EVENT, SchemaReloaded { pack: info, report })
+        }
+        Err(err) => {
+            println!("Пакет {:?} не перезагружен: {}\n------------", path, err);
+
+            app.emit_all(SCHEMA_RELOAD_FAILED_EVENT, SchemaReloadFailed {
+                path: path.to_path_buf(),
+                error: err.to_payload()
+            })
+        }
+    };
+
+    if let Err(err) = emitted {
+        println!("Не удалось отправить событие перезагрузки схемы: {}\n------------", err);
+    }
+}
+
+/// Следит за файлом схемы и каталогом пакетов. Следим за каталогами, а не
+/// за файлами: редакторы и [`CoefficientScheme::save`] заменяют файл
+/// переименованием.
+fn watch_schema(app: AppHandle, config: PathBuf, packs_dir: PathBuf) -> notify::Result<RecommendedWatcher> {
+
+    let (tx, rx) = mpsc::channel::<notify::Result<notify::Event>>();
+    let mut watcher = notify::recommended_watcher(tx)?;
+
+    let config_dir = config
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    watcher.watch(config_dir, RecursiveMode::NonRecursive)?;
+    if packs_dir.is_dir() {
+        watcher.watch(&packs_dir, RecursiveMode::NonRecursive)?;
+    }
+
+    // Путь события может быть абсолютным, поэтому сравниваем имена и
+    // возвращаем путь пакета в том виде, в каком он был загружен
+    let pack_path = move |path: &Path| -> Option<PathBuf> {
+        let file_name = path.file_name()?;
+
+        if path.parent().and_then(Path::file_name) == packs_dir.file_name() {
+            let is_pack = path.extension().map_or(false, |extension| extension == PACK_EXTENSION);
+            return is_pack.then(|| packs_dir.join(file_name));
+        }
+
+        return (Some(file_name) == config.file_name()).then(|| config.clone());
+    };
+
+    let changed_packs = move |event: notify::Result<notify::Event>| -> Vec<PathBuf> {
+        match event {
+            Ok(event) if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) =>
+                event.paths.iter().filter_map(|path| pack_path(path)).collect(),
+            Ok(_) => vec![],
+            Err(err) => {
+                println!("Ошибка наблюдения за схемой: {}\n------------", err);
+                vec![]
+            }
+        }
+    };
+
+    thread::spawn(move || {
+        while let Ok(event) = rx.recv() {
+            let mut changed = changed_packs(event).into_iter().collect::<BTreeSet<PathBuf>>();
+            if changed.is_empty() {
+                continue
+            }
+
+            while let Ok(event) = rx.recv_timeout(RELOAD_DEBOUNCE) {
+                changed.extend(changed_packs(event));
+            }
+
+            for path in changed {
+                reload_pack(&app, &path);
+            }
+        }
+    });
+
+    return Ok(watcher);
+}
+
+/// Копирует схему из ресурсов приложения в каталог данных при первом
+/// запуске, когда там нет ни схемы, ни каталога пакетов
+fn install_default_schema(resolver: &PathResolver, config: &Path, packs_dir: &Path) -> Result<(), AppError> {
+
+    if config.exists() || packs_dir.is_dir() {
+        return Ok(());
     }
+
+    let bundled = resolver
+        .resolve_resource(SCHEMA_FILE)
+        .ok_or(AppError::NotFound { entity: "Ресурс", name: SCHEMA_FILE.to_owned() })?;
+    if let Some(dir) = config.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::copy(&bundled, config)?;
+    println!("Схема скопирована из {:?} в {:?}\n------------", bundled, config);
+
+    return Ok(());
+}
+
+/// Режим `--check [путь]`: печатает все проблемы схемы и возвращает код выхода
+fn check_schema(config: &Path) -> i32 {
+
+    let schema = match File::open(config)
+        .map_err(SchemaError::from)
+        .and_then(CoefficientScheme::new) {
+        Ok(schema) => schema,
+        Err(err) => {
+            eprintln!("{}", err);
+            return 2;
+        }
+    };
+
+    let report = schema.validate();
+    println!("{}", report);
+
+    return if report.has_errors() { 1 } else { 0 };
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorkerRequest {
     name: String,
-    skills: Vec<String>
+    skills: WorkerSkills
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorkerResponse {
     pub name: String,
-    pub vacancies: BTreeMap<String, i64>
+    pub strategy: ScoringMethod,
+    /// Вакансии от лучшей к худшей
+    pub vacancies: Vec<VacancyScore>
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlacementRequest {
     company_name: String,
+    /// Должность в дереве компании → работник. `null` — должность не заполнена.
+    /// Работников разбирает [`PlacedWorker::from_value`], а не Tauri.
     placements: HashMap<String, Value>
 }
 
 #[tauri::command]
-fn get_skills(app: State<'_, Mutex<ManagementApp>>) -> HashSet<Skill> {
-
-    let schema = app.lock().unwrap();
-    let skills = schema.schema.get_skills().clone();
+fn get_skills(app: State<'_, Mutex<ManagementApp>>, pack: Option<String>) -> Result<Vec<SkillView>, AppError> {
+
+    let app = lock(&app);
+    let schema = app.pack(pack.as_deref())?.get_schema();
+    let skills = schema
+        .get_skills()
+        .iter()
+        .map(|skill| schema.get_skill_view(skill))
+        .collect::<Vec<SkillView>>();
     println!("Возвращены навыки\n------------");
 
-    return skills;
+    return Ok(skills);
 }
 
 #[tauri::command]
-fn get_vacancies(app: State<'_, Mutex<ManagementApp>>) -> HashSet<Vacancy> {
+fn validate_schema(app: State<'_, Mutex<ManagementApp>>, pack: Option<String>) -> Result<ValidationReport, AppError> {
 
-    let schema = app.lock().unwrap();
-    let vacancies = schema.schema.get_vacancies().clone();
-    println!("Возвращены должности\n------------");
+    let report = lock(&app).pack(pack.as_deref())?.get_schema().validate();
+    println!("Возвращен отчёт валидации\n------------");
 
-    return vacancies;
+    return Ok(report);
 }
 
+/// Переписывает файл схемы в актуальной версии формата и возвращает
+/// версию, в которой он был до этого
 #[tauri::command]
-fn check_placement(app: State<'_, Mutex<ManagementApp>>, data: PlacementRequest) -> f64 {
+fn upgrade_schema(app: State<'_, Mutex<ManagementApp>>, pack: Option<String>) -> Result<u32, AppError> {
 
-    let schema = &app.lock().unwrap().schema;
-    let current_company_opt = schema
-        .get_companies()
-        .get(&data.company_name);
+    let app = lock(&app);
+    let path = app.pack(pack.as_deref())?.get_path();
+    let source_version = upgrade_schema_file(path)?;
+    println!(
+        "Схема {:?} обновлена с версии {} до {}\n------------",
+        path, source_version, LATEST_SCHEMA_VERSION
+    );
+
+    return Ok(source_version);
+}
 
-    let company;
+/// Правит копию схемы пакета и записывает её в файл пакета. Пакет
+/// подменяется только после успешной записи.
+fn edit_schema<T>(
+    app: &State<'_, Mutex<ManagementApp>>,
+    pack: Option<String>,
+    edit: impl FnOnce(&mut CoefficientScheme) -> Result<T, AuthoringError>)
+    -> Result<T, AppError> {
 
-    if let Some(current_company) = current_company_opt {
-        company = current_company.clone();
-    } else {
-        return 0.0
+    let mut app = lock(app);
+    let current = app.pack(pack.as_deref())?;
+    let mut schema = current.get_schema().clone();
+
+    let result = edit(&mut schema)?;
+    schema.save(current.get_path())?;
+
+    let edited = SchemaPack::new(current.get_name().clone(), current.get_path().to_path_buf(), schema);
+    app.packs.insert(edited);
+
+    return Ok(result);
+}
+
+#[tauri::command]
+fn get_schema_packs(app: State<'_, Mutex<ManagementApp>>) -> Result<Vec<PackInfo>, AppError> {
+
+    let packs = lock(&app).packs.infos();
+    println!("Возвращены пакеты схем\n------------");
+
+    return Ok(packs);
+}
+
+/// Выбирает пакет схемы, по которому будут считаться результаты сессии.
+/// После выдачи теста пакет сессии не меняется.
+#[tauri::command]
+fn select_schema_pack(
+    app: State<'_, Mutex<ManagementApp>>,
+    storage: State<'_, Mutex<Storage>>,
+    session_id: i64,
+    pack: String)
+    -> Result<PackInfo, AppError> {
+
+    let mut storage = lock(&storage);
+    let app = lock(&app);
+
+    let selected = app.pack(Some(&pack))?;
+    storage.select_schema_pack(session_id, selected.get_name())?;
+    println!("Сессии {} выбран пакет {}\n------------", session_id, pack);
+
+    return Ok(app.packs.info(selected));
+}
+
+/// Сохраняет пакет в архив. Место выбирает пользователь в диалоге,
+/// `None` в ответе — диалог закрыт без выбора.
+#[tauri::command]
+async fn export_schema_pack(
+    app: State<'_, Mutex<ManagementApp>>,
+    pack: Option<String>,
+    author: Option<String>)
+    -> Result<Option<PackManifest>, AppError> {
+
+    // Копия пакета, чтобы не держать блокировку, пока открыт диалог
+    let selected = lock(&app).pack(pack.as_deref())?.clone();
+
+    let path = FileDialogBuilder::new()
+        .set_title("Экспорт пакета схемы")
+        .set_file_name(&format!("{}.{}", selected.get_name(), ARCHIVE_EXTENSION))
+        .add_filter("Архив пакета схемы", &[ARCHIVE_EXTENSION])
+        .save_file();
+    let path = match path {
+        Some(path) => path,
+        None => return Ok(None)
+    };
+
+    let manifest = selected.export_archive(File::create(&path)?, author)?;
+    println!("Пакет {} экспортирован в {:?}\n------------", manifest.name, path);
+
+    return Ok(Some(manifest));
+}
+
+/// Проверяет архив пакета и устанавливает его в каталог пакетов. Архив
+/// выбирает пользователь в диалоге, `None` в ответе — диалог закрыт без выбора.
+#[tauri::command]
+async fn import_schema_pack(app: State<'_, Mutex<ManagementApp>>) -> Result<Option<PackInfo>, AppError> {
+
+    let path = FileDialogBuilder::new()
+        .set_title("Импорт пакета схемы")
+        .add_filter("Архив пакета схемы", &[ARCHIVE_EXTENSION])
+        .pick_file();
+    let path = match path {
+        Some(path) => path,
+        None => return Ok(None)
+    };
+
+    let archive = PackArchive::read(io::BufReader::new(File::open(&path)?))?;
+
+    // Проверка и установка под одной блокировкой, чтобы два импорта одного
+    // пакета не прошли проверку оба. Пакет может быть загружен не из
+    // каталога пакетов, например skill_coefficients.json
+    let mut app = lock(&app);
+    if app.packs.get(&archive.manifest.name).is_some() {
+        return Err(ArchiveError::PackExists(archive.manifest.name).into());
     }
-    println!("{:?}", company);
 
-    let vacancies_tree = company.tree();
-    let vacancies_vec = vacancies_tree
-        .get_iter()
-        .collect::<Vec<JobLevel>>();
+    let pack = archive.install(&app.packs_dir)?;
+    let info = app.packs.info(&pack);
+    app.packs.insert(pack);
+    println!("Пакет {} импортирован из {:?}\n------------", info.name, path);
+
+    return Ok(Some(info));
+}
+
+/// Схема целиком, в формате файла: для редактора контента
+#[tauri::command]
+fn get_schema_document(app: State<'_, Mutex<ManagementApp>>, pack: Option<String>) -> Result<SchemaDocument, AppError> {
+
+    let document = lock(&app).pack(pack.as_deref())?.get_schema().to_document();
+    println!("Возвращен документ схемы\n------------");
+
+    return Ok(document);
+}
+
+#[tauri::command]
+fn add_vacancy(app: State<'_, Mutex<ManagementApp>>, pack: Option<String>, name: String, description: Option<String>) -> Result<(), AppError> {
+
+    edit_schema(&app, pack, |schema| schema.add_vacancy(name.clone(), description))?;
+    println!("Добавлена вакансия {}\n------------", name);
+
+    return Ok(());
+}
+
+#[tauri::command]
+fn update_vacancy(
+    app: State<'_, Mutex<ManagementApp>>,
+    pack: Option<String>,
+    name: String,
+    new_name: String,
+    description: Option<String>)
+    -> Result<(), AppError> {
+
+    edit_schema(&app, pack, |schema| schema.update_vacancy(&name, new_name.clone(), description))?;
+    println!("Вакансия {} изменена, теперь {}\n------------", name, new_name);
+
+    return Ok(());
+}
+
+/// `cascade` удаляет и коэффициенты навыков, которые ссылаются на вакансию
+#[tauri::command]
+fn remove_vacancy(app: State<'_, Mutex<ManagementApp>>, pack: Option<String>, name: String, cascade: Option<bool>) -> Result<(), AppError> {
+
+    edit_schema(&app, pack, |schema| schema.remove_vacancy(&name, cascade.unwrap_or(false)))?;
+    println!("Удалена вакансия {}\n------------", name);
+
+    return Ok(());
+}
 
-    let mut percentage_correctness: f64 = 0.0;
-    let mut all_percentage: f64 = 0.0;
+#[tauri::command]
+fn set_skill(
+    app: State<'_, Mutex<ManagementApp>>,
+    pack: Option<String>,
+    name: String,
+    coefficients: BTreeMap<String, i64>)
+    -> Result<(), AppError> {
+
+    edit_schema(&app, pack, |schema| schema.set_skill(name.clone(), coefficients))?;
+    println!("Сохранён навык {}\n------------", name);
+
+    return Ok(());
+}
 
-    for job_level in vacancies_vec {
+#[tauri::command]
+fn rename_skill(app: State<'_, Mutex<ManagementApp>>, pack: Option<String>, name: String, new_name: String) -> Result<(), AppError> {
+
+    edit_schema(&app, pack, |schema| schema.rename_skill(&name, new_name.clone()))?;
+    println!("Навык {} переименован в {}\n------------", name, new_name);
+
+    return Ok(());
+}
+
+#[tauri::command]
+fn remove_skill(app: State<'_, Mutex<ManagementApp>>, pack: Option<String>, name: String) -> Result<(), AppError> {
 
-        let (company_vacancy_name, target_vacancy_name) = job_level.label().iter().next().unwrap();
-        let worker_data = data.placements.get(company_vacancy_name).unwrap_or(&Value::Null);
+    edit_schema(&app, pack, |schema| schema.remove_skill(&name))?;
+    println!("Удалён навык {}\n------------", name);
+
+    return Ok(());
+}
+
+#[tauri::command]
+fn set_company(app: State<'_, Mutex<ManagementApp>>, pack: Option<String>, name: String, tree: PositionDocument) -> Result<(), AppError> {
+
+    edit_schema(&app, pack, |schema| schema.set_company(name.clone(), tree))?;
+    println!("Сохранена компания {}\n------------", name);
+
+    return Ok(());
+}
+
+#[tauri::command]
+fn rename_company(app: State<'_, Mutex<ManagementApp>>, pack: Option<String>, name: String, new_name: String) -> Result<(), AppError> {
+
+    edit_schema(&app, pack, |schema| schema.rename_company(&name, new_name.clone()))?;
+    println!("Компания {} переименована в {}\n------------", name, new_name);
+
+    return Ok(());
+}
 
-        if *worker_data == Value::Null {
-            println!("Должность {} не проставлена на графе и не найдена в RequestData", company_vacancy_name);
-            continue
+#[tauri::command]
+fn remove_company(app: State<'_, Mutex<ManagementApp>>, pack: Option<String>, name: String) -> Result<(), AppError> {
+
+    edit_schema(&app, pack, |schema| schema.remove_company(&name))?;
+    println!("Удалена компания {}\n------------", name);
+
+    return Ok(());
+}
+
+/// Возвращает uuid добавленного вопроса
+#[tauri::command]
+fn add_question(app: State<'_, Mutex<ManagementApp>>, pack: Option<String>, question: QuestionDocumentV2) -> Result<String, AppError> {
+
+    let uuid = edit_schema(&app, pack, |schema| schema.add_question(question))?;
+    println!("Добавлен вопрос {}\n------------", uuid);
+
+    return Ok(uuid);
+}
+
+#[tauri::command]
+fn update_question(app: State<'_, Mutex<ManagementApp>>, pack: Option<String>, uuid: String, question: QuestionDocumentV2) -> Result<(), AppError> {
+
+    edit_schema(&app, pack, |schema| schema.update_question(&uuid, question))?;
+    println!("Изменён вопрос {}\n------------", uuid);
+
+    return Ok(());
+}
+
+#[tauri::command]
+fn remove_question(app: State<'_, Mutex<ManagementApp>>, pack: Option<String>, uuid: String) -> Result<(), AppError> {
+
+    edit_schema(&app, pack, |schema| schema.remove_question(&uuid))?;
+    println!("Удалён вопрос {}\n------------", uuid);
+
+    return Ok(());
+}
+
+fn is_xlsx(path: &Path) -> bool {
+    path.extension().map_or(false, |extension| extension.eq_ignore_ascii_case(XLSX_EXTENSION))
+}
+
+/// Читает матрицу навык → вакансия из `.xlsx` или `.csv` и возвращает её
+/// с отчётом о ячейках. С `apply` матрица без ошибок заменяет вакансии и
+/// навыки пакета. Вакансии, которых нет в схеме, — ошибка в отчёте, если
+/// не передан `add_vacancies`. Файл выбирает пользователь в диалоге, `None`
+/// в ответе — диалог закрыт без выбора.
+#[tauri::command]
+async fn import_coefficient_matrix(
+    app: State<'_, Mutex<ManagementApp>>,
+    pack: Option<String>,
+    apply: bool,
+    add_vacancies: bool)
+    -> Result<Option<MatrixImport>, AppError> {
+
+    let path = FileDialogBuilder::new()
+        .set_title("Импорт матрицы коэффициентов")
+        .add_filter("Матрица коэффициентов", &[XLSX_EXTENSION, CSV_EXTENSION])
+        .pick_file();
+    let path = match path {
+        Some(path) => path,
+        None => return Ok(None)
+    };
+
+    let matrix = {
+        let app = lock(&app);
+        let schema = app.pack(pack.as_deref())?.get_schema();
+        let file = io::BufReader::new(File::open(&path)?);
+
+        if is_xlsx(&path) {
+            MatrixImport::from_xlsx(file, schema, add_vacancies)?
+        } else {
+            MatrixImport::from_csv(file, schema, add_vacancies)?
         }
+    };
+    println!(
+        "Матрица из {:?}: навыков {}, вакансий {}\n------------",
+        path, matrix.skills.len(), matrix.vacancies.len()
+    );
+
+    if apply {
+        edit_schema(&app, pack, |schema| schema.set_matrix(&matrix))?;
+        println!("Матрица из {:?} применена к схеме\n------------", path);
+    }
 
-        let worker_vacancies_top = worker_data["vacancies"].as_array().unwrap();
+    return Ok(Some(matrix));
+}
 
-        worker_vacancies_top.iter().enumerate().map(|(ind, vacancy)| {
+/// Сохраняет матрицу навык → вакансия пакета: `.csv` — текстом, иначе XLSX.
+/// Место выбирает пользователь в диалоге, `None` в ответе — диалог закрыт
+/// без выбора.
+#[tauri::command]
+async fn export_coefficient_matrix(
+    app: State<'_, Mutex<ManagementApp>>,
+    pack: Option<String>)
+    -> Result<Option<PathBuf>, AppError> {
+
+    let selected = lock(&app).pack(pack.as_deref())?.clone();
+
+    let path = FileDialogBuilder::new()
+        .set_title("Экспорт матрицы коэффициентов")
+        .set_file_name(&format!("{}.{}", selected.get_name(), XLSX_EXTENSION))
+        .add_filter("Книга Excel", &[XLSX_EXTENSION])
+        .add_filter("CSV", &[CSV_EXTENSION])
+        .save_file();
+    let path = match path {
+        Some(path) => path,
+        None => return Ok(None)
+    };
 
-            println!("{} ?==? {}\n", vacancy, target_vacancy_name);
-            let vacancy = vacancy.as_str().unwrap();
-            if vacancy == target_vacancy_name {
-                percentage_correctness += 1.0/(1.0+ind as f64);
-                println!("{}", percentage_correctness);
-            }
-        }).for_each(drop);
+    if is_xlsx(&path) {
+        fs::write(&path, selected.get_schema().matrix_xlsx()?)?;
+    } else {
+        selected.get_schema().write_matrix_csv(io::BufWriter::new(File::create(&path)?))?;
+    }
+    println!("Матрица пакета {} сохранена в {:?}\n------------", selected.get_name(), path);
 
-        all_percentage += 1.0;
+    return Ok(Some(path));
+}
 
-        println!("Company vacancy name: {}, target vacancy name: {}. Worker top vacancies: {:?}", company_vacancy_name, target_vacancy_name, worker_vacancies_top);
+#[tauri::command]
+fn get_vacancies(app: State<'_, Mutex<ManagementApp>>, pack: Option<String>) -> Result<HashSet<Vacancy>, AppError> {
+
+    let vacancies = lock(&app)
+        .pack(pack.as_deref())?
+        .get_schema()
+        .get_vacancies()
+        .iter()
+        .map(|(_, vacancy)| vacancy.clone())
+        .collect::<HashSet<Vacancy>>();
+    println!("Возвращены должности\n------------");
+
+    return Ok(vacancies);
+}
+
+#[tauri::command]
+fn check_placement(
+    app: State<'_, Mutex<ManagementApp>>,
+    storage: State<'_, Mutex<Storage>>,
+    data: PlacementRequest,
+    config: Option<PlacementConfig>,
+    session_id: Option<i64>)
+    -> Result<PlacementReport, AppError> {
+
+    let placements = data.placements
+        .into_iter()
+        .filter(|(_, worker)| !worker.is_null())
+        .map(|(position, worker)| PlacedWorker::from_value(&position, worker).map(|worker| (position, worker)))
+        .collect::<Result<HashMap<String, PlacedWorker>, PlacementError>>()?;
+
+    let mut storage = lock(&storage);
+    let app = lock(&app);
+    let pack = match session_id {
+        Some(session_id) => session_pack(&app, &mut storage, session_id)?,
+        None => app.pack(None)?
+    };
+
+    let report = pack
+        .get_schema()
+        .grade_placement(&data.company_name, &placements, &config.unwrap_or_default())?;
+    println!(
+        "Проверена расстановка в компании {}: {} / {}\n------------",
+        report.company, report.correctness, report.score
+    );
+
+    if let Some(session_id) = session_id {
+        storage.record_placement(session_id, &PlacementScoreRecord {
+            company: Some(report.company.clone()),
+            score: report.score
+        })?;
     }
 
-    println!("{} / {}", percentage_correctness, all_percentage);
-    return percentage_correctness * 0.25 / all_percentage;
+    return Ok(report);
+}
 
+#[derive(Debug, Clone, Serialize)]
+pub struct SolvePlacementResponse {
+    solution: PlacementSolution,
+    /// Пригодность расстановки студента по той же метрике, если она передана
+    student_fit: Option<f64>
 }
 
 #[tauri::command]
-fn get_questions(app: State<'_, Mutex<ManagementApp>>) -> HashSet<Question> {
+fn solve_placement(
+    app: State<'_, Mutex<ManagementApp>>,
+    company_name: String,
+    workers: Vec<WorkerResponse>,
+    placement: Option<HashMap<String, String>>,
+    pack: Option<String>)
+    -> Result<SolvePlacementResponse, AppError> {
+
+    let app = lock(&app);
+    let company = app
+        .pack(pack.as_deref())?
+        .get_schema()
+        .get_companies()
+        .get(&company_name)
+        .ok_or(AppError::NotFound { entity: "Компания", name: company_name.clone() })?;
 
-    let schema = app.lock().unwrap();
-    let all_questions =  schema.schema.get_questions().clone();
-    let sample: Vec<&Question> = all_questions.iter()
-        .choose_multiple(&mut rand::thread_rng(), 15);
+    let workers = workers
+        .into_iter()
+        .map(|worker| ScoredWorker {
+            name: worker.name,
+            scores: worker.vacancies
+                .into_iter()
+                .map(|score| (score.vacancy.0, score.score))
+                .collect()
+        })
+        .collect::<Vec<ScoredWorker>>();
 
-    let d: Vec<_> = sample.iter().map(|q|(*q).clone()).collect();
+    let solution = company.solve_placement(&workers);
+    let student_fit = placement.map(|placement| company.placement_fit(&placement, &workers));
 
-    return HashSet::from_iter(d.into_iter());
+    println!(
+        "Найдена оптимальная расстановка в компании {}: {}\n------------",
+        solution.company, solution.total_fit
+    );
 
+    return Ok(SolvePlacementResponse {
+        solution,
+        student_fit
+    });
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct QuestionAnswerResponse {
-    question_uuid: String,
-    answers: Vec<String>
+#[derive(Debug, Clone, Serialize)]
+pub struct IssuedQuizResponse {
+    /// С этими seed и планом тест можно выдать повторно
+    seed: u64,
+    blueprint: QuizBlueprint,
+    questions: Vec<QuestionView>
 }
 
+/// Задаёт план теста сессии. Вызывается из окна преподавателя до выдачи
+/// вопросов, студент план не выбирает.
 #[tauri::command]
-fn get_questions_answers(app: State<'_, Mutex<ManagementApp>>, answers: Vec<QuestionAnswerResponse>) -> f64 {
+fn set_session_blueprint(
+    storage: State<'_, Mutex<Storage>>,
+    session_id: i64,
+    blueprint: QuizBlueprint)
+    -> Result<AssessmentSession, AppError> {
 
-    let schema = app.lock().unwrap();
-    let questions_target = schema.schema.get_questions();
+    let mut storage = lock(&storage);
+    storage.set_quiz_blueprint(session_id, &blueprint)?;
+    println!("Сессии {} задан план теста\n------------", session_id);
 
-    let all_answer = answers.len();
-    let mut correct_count = 0;
+    return Ok(storage.get_session(session_id)?);
+}
 
-    for answer in answers {
-        let question_target = questions_target.get(&answer.question_uuid);
-        match question_target {
-            None => continue,
-            Some(question_target) => {
+/// Выдаёт тест сессии по плану и seed и запоминает выданные вопросы.
+/// Оценивать можно будет только эти вопросы.
+fn issue_quiz(
+    pack: &SchemaPack,
+    storage: &mut Storage,
+    quizzes: &mut QuizSessions,
+    session_id: i64,
+    blueprint: QuizBlueprint,
+    seed: u64)
+    -> Result<IssuedQuizResponse, AppError> {
+
+    let questions = pack.get_schema().render_quiz(&blueprint, seed)?;
+
+    // Сначала база: она помнит выданный тест и после перезапуска приложения
+    storage.record_quiz_issue(session_id, seed, &blueprint, pack.get_name(), pack.get_version())?;
+    quizzes.issue(session_id, questions.iter().map(|question| question.uuid.clone()).collect())?;
+    println!("Выданы вопросы в сессии {} (seed {})\n------------", session_id, seed);
+
+    return Ok(IssuedQuizResponse {
+        seed,
+        blueprint,
+        questions
+    });
+}
 
-                let question_target_variants = question_target.get_variants();
+/// Выдаёт студенту вопросы по плану теста сессии без признака правильности
+/// ответов. Тест выдаётся один раз, seed выбирает бэкенд. Повторный вызов,
+/// в том числе после перезапуска приложения, возвращает те же вопросы.
+#[tauri::command]
+fn get_questions(
+    app: State<'_, Mutex<ManagementApp>>,
+    storage: State<'_, Mutex<Storage>>,
+    quizzes: State<'_, Mutex<QuizSessions>>,
+    session_id: i64)
+    -> Result<IssuedQuizResponse, AppError> {
+
+    let mut storage = lock(&storage);
+    let mut quizzes = lock(&quizzes);
+    let app = lock(&app);
+
+    let session = storage.get_open_session(session_id)?;
+    let pack = session_pack(&app, &mut storage, session_id)?;
+
+    if let Some(seed) = session.quiz_seed {
+        let questions = quizzes.resume(&session, pack.get_schema(), storage.has_quiz_answers(session_id)?)?;
+        println!("Повторно выданы вопросы в сессии {} (seed {})\n------------", session_id, seed);
+
+        return Ok(IssuedQuizResponse {
+            seed,
+            blueprint: session.quiz_blueprint.unwrap_or_default(),
+            questions
+        });
+    }
 
-                let result = answer.answers
-                    .iter()
-                    .fold(true, |res, answer| {
-                        let answer_res = question_target_variants
-                            .get(answer)
-                            .map_or(false, |a| a.get_answer_state());
+    // Не больше 2^53, чтобы seed без потерь пережил число в JavaScript
+    let seed = rand::thread_rng().gen_range(0..1u64 << 53);
 
-                        return match answer_res {
-                            true => res,
-                            false => false
-                        }
+    return issue_quiz(pack, &mut storage, &mut quizzes, session_id, session.quiz_blueprint.unwrap_or_default(), seed);
+}
 
-                    });
+/// Выдаёт в сессии тот же тест, что в сессии `from_session_id`: её план,
+/// seed и пакет схемы. Вопросы и порядок вариантов совпадут, если пакет
+/// с тех пор не менялся.
+#[tauri::command]
+fn reissue_quiz(
+    app: State<'_, Mutex<ManagementApp>>,
+    storage: State<'_, Mutex<Storage>>,
+    quizzes: State<'_, Mutex<QuizSessions>>,
+    from_session_id: i64,
+    session_id: i64)
+    -> Result<IssuedQuizResponse, AppError> {
 
-                println!("{}", result);
+    let mut storage = lock(&storage);
+    let mut quizzes = lock(&quizzes);
+    let app = lock(&app);
 
-                if result {
-                    correct_count += 1;
-                }
-            }
+    let source = storage.get_session(from_session_id)?;
+    let seed = source.quiz_seed.ok_or(QuizError::NotIssued { session_id: from_session_id })?;
+    let pack = app.pack(source.schema_pack.as_deref())?;
+
+    return issue_quiz(pack, &mut storage, &mut quizzes, session_id, source.quiz_blueprint.unwrap_or_default(), seed);
+}
+
+/// Оценивает ответы на вопросы, выданные в сессии, и сохраняет результат
+#[tauri::command]
+fn get_questions_answers(
+    app: State<'_, Mutex<ManagementApp>>,
+    storage: State<'_, Mutex<Storage>>,
+    quizzes: State<'_, Mutex<QuizSessions>>,
+    session_id: i64,
+    answers: Vec<SubmittedAnswer>)
+    -> Result<QuizGrade, AppError> {
+
+    let mut storage = lock(&storage);
+    let mut quizzes = lock(&quizzes);
+    let app = lock(&app);
+
+    let session = storage.get_open_session(session_id)?;
+    let schema = session_pack(&app, &mut storage, session_id)?.get_schema();
+    // После перезапуска приложения выданные вопросы восстанавливаются из базы
+    quizzes.restore(&session, schema, storage.has_quiz_answers(session_id)?)?;
+
+    let grade = quizzes.grade(session_id, schema, &answers)?;
+    // Тест закрывается только после записи: при ошибке базы ответы можно отправить снова
+    storage.record_quiz_answers(session_id, &grade.to_records())?;
+    quizzes.mark_submitted(session_id)?;
+
+    println!("Тест сессии {}: {} из {}\n------------", session_id, grade.points, grade.max_points);
+
+    return Ok(grade);
+}
+
+/// Начинает адаптивный тест: каждый следующий вопрос подбирается по текущей
+/// оценке способностей студента и сложности вопросов с учётом прошлых
+/// результатов. Начатый тест продолжается с текущего вопроса, в том числе
+/// после перезапуска приложения.
+#[tauri::command]
+fn start_adaptive_quiz(
+    app: State<'_, Mutex<ManagementApp>>,
+    storage: State<'_, Mutex<Storage>>,
+    adaptive: State<'_, Mutex<AdaptiveSessions>>,
+    session_id: i64,
+    length: Option<usize>)
+    -> Result<AdaptiveStep, AppError> {
+
+    let mut storage = lock(&storage);
+    let mut adaptive = lock(&adaptive);
+    let app = lock(&app);
+
+    let session = storage.get_open_session(session_id)?;
+    let pack = session_pack(&app, &mut storage, session_id)?;
+    let statistics = storage.question_statistics()?;
+
+    if session.adaptive_length.is_some() {
+        adaptive.restore(&session, pack.get_schema(), &statistics, &storage.session_quiz_answers(session_id)?);
+        let step = adaptive.current(session_id, pack.get_schema())?;
+        println!("Продолжен адаптивный тест в сессии {}\n------------", session_id);
+
+        return Ok(step);
+    }
+
+    // Сначала база: она не даст начать тест, если в сессии выдан обычный
+    let length = length.unwrap_or(DEFAULT_ADAPTIVE_LENGTH);
+    storage.record_adaptive_start(session_id, length, pack.get_name(), pack.get_version())?;
+
+    let step = adaptive.start(session_id, pack.get_schema(), &statistics, length)?;
+    println!("Начат адаптивный тест в сессии {}\n------------", session_id);
+
+    return Ok(step);
+}
+
+/// Принимает ответ на текущий вопрос адаптивного теста, сохраняет его
+/// и возвращает следующий вопрос
+#[tauri::command]
+fn answer_adaptive_question(
+    app: State<'_, Mutex<ManagementApp>>,
+    storage: State<'_, Mutex<Storage>>,
+    adaptive: State<'_, Mutex<AdaptiveSessions>>,
+    session_id: i64,
+    answer: SubmittedAnswer)
+    -> Result<AdaptiveStep, AppError> {
+
+    let mut storage = lock(&storage);
+    let step = {
+        let mut adaptive = lock(&adaptive);
+        let app = lock(&app);
+
+        let session = storage.get_open_session(session_id)?;
+        let schema = session_pack(&app, &mut storage, session_id)?.get_schema();
+
+        // После перезапуска приложения тест восстанавливается из базы
+        if adaptive.get(session_id).is_none() {
+            let statistics = storage.question_statistics()?;
+            adaptive.restore(&session, schema, &statistics, &storage.session_quiz_answers(session_id)?);
         }
 
+        adaptive.answer(session_id, schema, answer)?
+    };
+
+    if let Some(grade) = &step.last {
+        storage.record_quiz_answers(session_id, &[grade.to_record()])?;
     }
+    storage.record_ability(session_id, step.ability)?;
+
+    println!(
+        "Адаптивный тест сессии {}: {} из {}, способности {:.2}\n------------",
+        session_id, step.answered, step.length, step.ability
+    );
+
+    return Ok(step);
+}
 
-    correct_count as f64 / all_answer as f64
+fn item_analysis(
+    app: &State<'_, Mutex<ManagementApp>>,
+    storage: &State<'_, Mutex<Storage>>,
+    pack: Option<String>)
+    -> Result<ItemAnalysisReport, AppError> {
 
+    let answers = lock(storage).all_quiz_answers()?;
+
+    return Ok(lock(app).pack(pack.as_deref())?.get_schema().analyze_items(&answers));
 }
 
+/// Анализ вопросов банка по сохранённым результатам
 #[tauri::command]
-fn get_companies(app: State<'_, Mutex<ManagementApp>>) -> Vec<String> {
+fn get_item_analysis(
+    app: State<'_, Mutex<ManagementApp>>,
+    storage: State<'_, Mutex<Storage>>,
+    pack: Option<String>)
+    -> Result<ItemAnalysisReport, AppError> {
+
+    let report = item_analysis(&app, &storage, pack)?;
+    println!("Возвращен анализ вопросов по {} сессиям\n------------", report.sessions);
 
-    let schema = app.lock().unwrap();
-    let companies = schema.schema.get_companies().iter().map(|company| company.name()).cloned().collect::<Vec<String>>();
+    return Ok(report);
+}
+
+/// Сохраняет анализ вопросов в файл: `.csv` — таблицей, иначе JSON. Место
+/// выбирает пользователь в диалоге, `None` в ответе — диалог закрыт без выбора.
+#[tauri::command]
+async fn export_item_analysis(
+    app: State<'_, Mutex<ManagementApp>>,
+    storage: State<'_, Mutex<Storage>>,
+    pack: Option<String>)
+    -> Result<Option<PathBuf>, AppError> {
+
+    let path = FileDialogBuilder::new()
+        .set_title("Экспорт анализа вопросов")
+        .set_file_name(&format!("Анализ вопросов.{}", CSV_EXTENSION))
+        .add_filter("CSV", &[CSV_EXTENSION])
+        .add_filter("JSON", &["json"])
+        .save_file();
+    let path = match path {
+        Some(path) => path,
+        None => return Ok(None)
+    };
+
+    let report = item_analysis(&app, &storage, pack)?;
+    let file = io::BufWriter::new(File::create(&path)?);
+
+    if path.extension().map_or(false, |extension| extension.eq_ignore_ascii_case(CSV_EXTENSION)) {
+        report.write_csv(file)?;
+    } else {
+        serde_json::to_writer_pretty(file, &report)?;
+    }
+    println!("Анализ вопросов сохранён в {:?}\n------------", path);
+
+    return Ok(Some(path));
+}
+
+#[tauri::command]
+fn get_companies(app: State<'_, Mutex<ManagementApp>>, pack: Option<String>) -> Result<Vec<String>, AppError> {
+
+    let app = lock(&app);
+    let companies = app.pack(pack.as_deref())?.get_schema().get_companies().iter().map(|company| company.name()).cloned().collect::<Vec<String>>();
 
     println!("Возвращены компании\n------------");
-    return companies;
+    return Ok(companies);
 }
 
 #[tauri::command]
-fn get_current_company(app: State<'_, Mutex<ManagementApp>>, company_name: String) -> Option<Company> {
+fn get_current_company(
+    app: State<'_, Mutex<ManagementApp>>,
+    company_name: String,
+    pack: Option<String>)
+    -> Result<Company, AppError> {
 
-    let schema = app.lock().unwrap();
-    let opt_company = schema.schema
+    let app = lock(&app);
+    let opt_company = app
+        .pack(pack.as_deref())?
+        .get_schema()
         .get_companies()
         .get(&company_name);
 
     if let Some(company) = opt_company {
         println!("Возвращена компании {}\n------------", company.name());
-        return Some(company.clone());
+        return Ok(company.clone());
     }
 
-    return None;
+    return Err(AppError::NotFound { entity: "Компания", name: company_name });
 }
 
 #[tauri::command]
 fn get_vacancies_for_worker(
     app: State<'_, Mutex<ManagementApp>>,
-    worker: WorkerRequest)
-    -> WorkerResponse {
+    storage: State<'_, Mutex<Storage>>,
+    worker: WorkerRequest,
+    strategy: Option<ScoringMethod>,
+    session_id: Option<i64>)
+    -> Result<WorkerResponse, AppError> {
 
-    let schema = app.lock().unwrap();
-    let schema_skills = schema.schema.get_skills();
+    let strategy = strategy.unwrap_or_default();
 
-    let mut vacancies_coefs: BTreeMap<String, i64> = BTreeMap::default();
+    let profile = WorkerProfile::from_levels(&worker.skills.levels())?;
 
-    for skill in &worker.skills {
+    let mut storage = lock(&storage);
+    let vacancies = {
+        let app = lock(&app);
+        let pack = match session_id {
+            Some(session_id) => session_pack(&app, &mut storage, session_id)?,
+            None => app.pack(None)?
+        };
 
-        let skill_info = schema_skills
-            .get(skill)
-            .ok_or(AppError::Custom {
-                name: "Not Found".into(),
-                description: "Skill not found in schema".into()
-            }).unwrap();
-
-        let vacancies_coef = skill_info.get_vacancies_coefficient();
+        pack.get_schema().score_vacancies(&profile, strategy.strategy().as_ref())?
+    };
 
-        for vac_coef in vacancies_coef {
+    println!("Возвращены должности для работника: {}\n------------", worker.name);
 
-            let vacancy_name = vac_coef.get_vacancy_name().clone();
-            let coef = vac_coef.get_coefficient();
+    if let Some(session_id) = session_id {
+        let strategy_name = serde_json::to_value(strategy)?
+            .as_str()
+            .unwrap_or_default()
+            .to_owned();
+
+        let records = vacancies
+            .iter()
+            .map(|score| VacancyScoreRecord {
+                worker: worker.name.clone(),
+                strategy: strategy_name.clone(),
+                vacancy: score.vacancy.0.clone(),
+                score: score.score
+            })
+            .collect::<Vec<VacancyScoreRecord>>();
 
-            vacancies_coefs
-                .entry(vacancy_name)
-                .and_modify(|e| {
-                    *e += coef;
-                })
-                .or_insert(coef);
-        }
+        storage.record_vacancy_scores(session_id, &records)?;
     }
 
-    // Сортировка коеффициентов у должностей
-    /*
-    let mut vac = vacancies_coefs
-        .clone()
-        .into_iter()
-        .collect::<Vec<(String, i64)>>();
-    vac.sort_by_key(|vac| vac.1);
-
-     */
-
-    println!("Возвращены должности для работника: {}\n------------", worker.name);
-
-    return WorkerResponse {
+    return Ok(WorkerResponse {
         name: worker.name,
-        vacancies: vacancies_coefs
-    };
+        strategy,
+        vacancies
+    });
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -296,18 +1245,7 @@ struct AnswerResultRequest {
     answer_result: bool
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct AnswerResultResponse {
-    question_title: String,
-    answer_result: bool
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct ResponseSaveAnswers {
-    answers: Vec<AnswerResultResponse>,
-    name: String
-}
-
+/// Формат записи старого ./result.json, нужен только для импорта
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct UserSaveResult {
     name: String,
@@ -315,154 +1253,249 @@ struct UserSaveResult {
     vacancy_results: Option<f64>,
 }
 
+impl UserSaveResult {
+    fn into_session(self) -> NewSession {
+        NewSession {
+            student_name: self.name,
+            answers: self.test_results
+                .unwrap_or_default()
+                .into_iter()
+                .map(|answer| QuizAnswerRecord::from_result(answer.question_uuid, answer.answer_result))
+                .collect(),
+            placement: self.vacancy_results.map(|score| PlacementScoreRecord {
+                company: None,
+                score
+            })
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct AllSave(pub Vec<UserSaveResult>);
 
-#[tauri::command]
-fn save_test(
-    app: State<'_, Mutex<ManagementApp>>,
-    tmp: State<'_, Mutex<Option<AllSave>>>,
-    name: String,
-    test_results: Option<Vec<AnswerResultRequest>>,
-    vacancy_results: Option<f64>)
-{
-    println!("Имя студента {:?}", name);
-    println!("Данные для сохранения теста {:?}", test_results);
-    println!("Данные для сохранения навыков {:?}", vacancy_results);
-
-    let user_save_state = UserSaveResult {
-        name,
-        test_results,
-        vacancy_results,
-    };
+/// Переносит результаты из ./result.json в базу и переименовывает файл,
+/// чтобы импорт не повторялся
+fn import_legacy_results(storage: &mut Storage, path: &Path) -> Result<usize, AppError> {
 
+    let save_all: AllSave = serde_json::from_reader(File::open(path)?)?;
+    let count = save_all.0.len();
 
-    if let Ok(mut f) = fs::File::open("./result.json") {
-        let mut save_all: AllSave = serde_json::from_reader(&f).unwrap();
-        save_all.0.push(user_save_state);
+    for result in save_all.0 {
+        storage.import_session(&result.into_session())?;
+    }
 
-        fs::write("./result.json", serde_json::to_vec(&save_all).unwrap()).unwrap();
+    fs::rename(path, path.with_extension("json.imported"))?;
 
-    } else {
-        let save_all = AllSave(vec![user_save_state]);
-        fs::write("./result.json", serde_json::to_vec(&save_all).unwrap()).unwrap();
-    }
+    return Ok(count);
+}
 
-    //*tmp.lock().unwrap() = Some(save);
+#[tauri::command]
+fn create_student(
+    storage: State<'_, Mutex<Storage>>,
+    name: String,
+    group: Option<String>)
+    -> Result<Student, AppError> {
 
-    return;
+    let student = lock(&storage).create_student(&name, group.as_deref())?;
+    println!("Создан студент {} ({:?})\n------------", student.name, student.id);
 
+    return Ok(student);
 }
 
 #[tauri::command]
-fn get_saved_result(tmp: State<'_, Mutex<Option<AllSave>>>) -> AllSave {
+fn get_students(
+    storage: State<'_, Mutex<Storage>>,
+    query: Option<String>,
+    group: Option<String>)
+    -> Result<Vec<Student>, AppError> {
 
-    return if let Ok(mut f) = fs::File::open("./result.json") {
-        let mut save_all: AllSave = serde_json::from_reader(&f).unwrap();
-        save_all
+    let students = lock(&storage).find_students(query.as_deref(), group.as_deref())?;
 
-    } else {
-        AllSave(vec![])
-    }
+    return Ok(students);
+}
 
+/// Начинает новую попытку студента. Её id передаётся в get_questions_answers,
+/// get_vacancies_for_worker и check_placement, чтобы их результаты сохранились.
+#[tauri::command]
+fn start_session(storage: State<'_, Mutex<Storage>>, student_id: i64) -> Result<AssessmentSession, AppError> {
+
+    let session = lock(&storage).start_session(student_id)?;
+    println!("Начата сессия {} студента {}\n------------", session.id, student_id);
 
+    return Ok(session);
 }
 
-fn main() {
+/// Завершает сессию. Результаты теста и расстановки к этому моменту уже
+/// сохранены командами get_questions_answers и check_placement.
+#[tauri::command]
+fn finish_session(storage: State<'_, Mutex<Storage>>, session_id: i64) -> Result<AssessmentSession, AppError> {
 
-    let management_app = ManagementApp::new(Path::new("./skill_coefficients.json")).unwrap();
+    let session = lock(&storage).finish_session(session_id)?;
+    println!("Завершена сессия {}\n------------", session_id);
 
-    tauri::Builder::default()
-        .manage(Mutex::new(None::<AllSave>))
-        .manage(Mutex::new(management_app))
-        .invoke_handler(tauri::generate_handler![
-            get_skills,
-            get_vacancies,
-            get_vacancies_for_worker,
-            get_companies,
-            check_placement,
-            get_current_company,
-            get_questions,
-            get_questions_answers,
-            save_test,
-            get_saved_result,
-        ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
-
-    //tests::test();
+    return Ok(session);
 }
 
-mod tests {
-    use std::path::Path;
-    use std::sync::Mutex;
-    use tauri::{Manager, State};
-    use crate::{check_placement, get_companies, get_current_company, get_questions, get_questions_answers, get_vacancies_for_worker, ManagementApp, PlacementRequest, QuestionAnswerResponse, WorkerRequest};
-
-    #[tauri::command]
-    fn get_skills_ww(app: State<'_, Mutex<ManagementApp>>) -> i64 {
-        return 2;
-    }
+#[tauri::command]
+fn get_saved_result(
+    storage: State<'_, Mutex<Storage>>,
+    filter: Option<ResultFilter>,
+    page: Option<Page>)
+    -> Result<ResultPage, AppError> {
 
-    pub fn test() {
+    let result_page = lock(&storage)
+        .query_sessions(&filter.unwrap_or_default(), &page.unwrap_or_default())?;
 
-        let app = tauri::Builder::default()
-            .setup(|app| {
+    return Ok(result_page);
+}
 
-                app.manage(Mutex::new(ManagementApp::new(Path::new("./skill_coefficients.json")).unwrap()));
+/// Сохраняет ведомость по сессиям, подходящим под фильтр: строка на сессию,
+/// балл теста, баллы за вопросы, балл расстановки и время. `.csv` — текстом,
+/// иначе XLSX. Место выбирает пользователь в диалоге, `None` в ответе —
+/// диалог закрыт без выбора.
+#[tauri::command]
+async fn export_gradebook(
+    app: State<'_, Mutex<ManagementApp>>,
+    storage: State<'_, Mutex<Storage>>,
+    filter: Option<ResultFilter>)
+    -> Result<Option<PathBuf>, AppError> {
 
-                let man_app = app.state::<Mutex<ManagementApp>>();
+    let file_name = match filter.as_ref().and_then(|filter| filter.group.as_ref()) {
+        Some(group) => format!("Ведомость {}.{}", group, XLSX_EXTENSION),
+        None => format!("Ведомость.{}", XLSX_EXTENSION)
+    };
 
-                // let result = get_vacancies_for_worker(man_app.clone(), WorkerRequest {
-                //     name: "Олег".to_string(),
-                //     skills: vec!["Надёжность".into(), "Спокойствие".into()],
-                // });
-                //
-                // println!("Result: {:?}", result);
+    let path = FileDialogBuilder::new()
+        .set_title("Экспорт ведомости")
+        .set_file_name(&file_name)
+        .add_filter("Книга Excel", &[XLSX_EXTENSION])
+        .add_filter("CSV", &[CSV_EXTENSION])
+        .save_file();
+    let path = match path {
+        Some(path) => path,
+        None => return Ok(None)
+    };
 
-                //println!("Result get_jobs: {:?}", serde_json::to_string(&get_current_company(man_app, "Разработка_ПО".into())));
-                //println!("Result get_jobs: {:?}", serde_json::to_string(&get_companies(man_app.clone())));
-                // println!(
-                //     "Result get_jobs: {:?}",
-                //     serde_json::to_string(
-                //         &get_current_company(
-                //             man_app,
-                //             "Системная_интеграция".into()
-                //         )
-                //     )
-                // );
+    let all = Page { offset: 0, limit: u32::MAX };
+    let sessions = lock(&storage).query_sessions(&filter.unwrap_or_default(), &all)?.items;
+    let gradebook = Gradebook::new(&sessions, &lock(&app).packs);
 
-                let json = r#"{"company_name":"Консалтинг","placements":{"Ьизнес-аналитик":{"name":"jrwj","qualities":["Пунктуальность","Спокойствие"],"id":4,"vacancies":["Analytic","Technical_Support","Manager","Programmer","QA_Engineer","Team_Lead"]},"Управляющий партнёр":{"name":"j","qualities":["Исполнительность","Ответственность"],"id":1,"vacancies":["Analytic","Manager","Programmer","Team_Lead","QA_Engineer"]},"Ведущий программист":{"name":"t","qualities":["Внимательность","Исполнительность"],"id":0,"vacancies":["System_Admin","Analytic","Programmer","QA_Engineer","Team_Lead"]},"Консультант":{"name":"jrwj","qualities":["Пунктуальность","Спокойствие"],"id":4,"vacancies":["HR_Manager","Technical_Support","Manager","Programmer","QA_Engineer","Team_Lead"]}}}"#;
-                let placement = serde_json::from_str::<PlacementRequest>(json).unwrap();
+    if is_xlsx(&path) {
+        fs::write(&path, gradebook.to_xlsx()?)?;
+    } else {
+        gradebook.write_csv(io::BufWriter::new(File::create(&path)?))?;
+    }
+    println!("Ведомость на {} сессий сохранена в {:?}\n------------", gradebook.rows.len(), path);
 
-                // println!(
-                //     "Result get_jobs: {:?}",
-                //     serde_json::to_string(&check_placement(man_app, placement, ))
-                // );
+    return Ok(Some(path));
+}
 
-                let questions = get_questions(man_app.clone());
-                let mut question_iter = questions.iter();
+fn main() {
 
-                let mut answers = vec![];
+    let context = tauri::generate_context!();
+
+    let args = std::env::args().collect::<Vec<String>>();
+    if let Some(ind) = args.iter().position(|arg| arg == "--check") {
+        let config = args
+            .get(ind + 1)
+            .map(PathBuf::from)
+            .or_else(|| tauri::api::path::app_data_dir(context.config()).map(|dir| dir.join(SCHEMA_FILE)));
+        let code = match config {
+            Some(config) => check_schema(&config),
+            None => {
+                eprintln!("Не удалось определить каталог данных приложения");
+                2
+            }
+        };
+        std::process::exit(code);
+    }
 
-                answers.push(QuestionAnswerResponse {
-                    question_uuid: question_iter.next().unwrap().get_uuid().clone(),
-                    answers: vec!["Да".into()],
-                });
-                answers.push(QuestionAnswerResponse {
-                    question_uuid: question_iter.next().unwrap().get_uuid().clone(),
-                    answers: vec!["Да".into()],
-                });
+    tauri::Builder::default()
+        .setup(|app| {
+            let data_dir = app
+                .path_resolver()
+                .app_data_dir()
+                .ok_or("Не удалось определить каталог данных приложения")?;
+            let config = data_dir.join(SCHEMA_FILE);
+            let packs_dir = data_dir.join(PACKS_DIR);
+
+            // Блокирующий диалог до запуска цикла событий зависает, поэтому
+            // ошибка показывается обычным диалогом, а выход — по его закрытию
+            let management_app = install_default_schema(&app.path_resolver(), &config, &packs_dir)
+                .and_then(|_| ManagementApp::new(&config, &packs_dir));
+            let management_app = match management_app {
+                Ok(management_app) => management_app,
+                Err(err) => {
+                    println!("Ошибка загрузки схемы: {}\n------------", err);
+                    let handle = app.handle();
+                    MessageDialogBuilder::new("Ошибка загрузки схемы", err.to_string())
+                        .kind(MessageDialogKind::Error)
+                        .show(move |_| handle.exit(1));
+                    return Ok(());
+                }
+            };
+            app.manage(Mutex::new(management_app));
 
-                let res = get_questions_answers(man_app, answers);
+            let mut storage = Storage::open(&data_dir.join("results.sqlite3"))?;
 
-                println!("{}", res);
+            let legacy_results = Path::new("./result.json");
+            if legacy_results.exists() && storage.is_empty()? {
+                let count = import_legacy_results(&mut storage, legacy_results)?;
+                println!("Импортировано {} результатов из {:?}\n------------", count, legacy_results);
+            }
 
-                Ok(())
+            app.manage(Mutex::new(storage));
 
-            })
-            .invoke_handler(tauri::generate_handler![get_skills_ww])
-            .run(tauri::generate_context!())
-            .expect("error while running tauri application");
-    }
+            let watcher = watch_schema(app.handle(), config, packs_dir)?;
+            app.manage(Mutex::new(watcher));
+            Ok(())
+        })
+        .manage(Mutex::new(QuizSessions::default()))
+        .manage(Mutex::new(AdaptiveSessions::default()))
+        .invoke_handler(tauri::generate_handler![
+            get_skills,
+            get_vacancies,
+            get_vacancies_for_worker,
+            get_companies,
+            check_placement,
+            solve_placement,
+            get_current_company,
+            set_session_blueprint,
+            get_questions,
+            reissue_quiz,
+            get_questions_answers,
+            start_adaptive_quiz,
+            answer_adaptive_question,
+            get_item_analysis,
+            export_item_analysis,
+            create_student,
+            get_students,
+            start_session,
+            finish_session,
+            get_saved_result,
+            export_gradebook,
+            validate_schema,
+            upgrade_schema,
+            get_schema_packs,
+            select_schema_pack,
+            export_schema_pack,
+            import_schema_pack,
+            get_schema_document,
+            add_vacancy,
+            update_vacancy,
+            remove_vacancy,
+            set_skill,
+            rename_skill,
+            remove_skill,
+            set_company,
+            rename_company,
+            remove_company,
+            add_question,
+            update_question,
+            remove_question,
+            import_coefficient_matrix,
+            export_coefficient_matrix,
+        ])
+        .run(context)
+        .expect("error while running tauri application");
 }